@@ -0,0 +1,86 @@
+use Universe;
+
+use wasm_bindgen::prelude::*;
+
+// what every cell starts at and slowly settles back towards
+pub const AMBIENT: i16 = 20;
+// water at or above this boils away
+pub const BOIL_POINT: i16 = 100;
+// each tick a cell moves 1 / SPREAD of the way to its neighbours' average
+const SPREAD: i32 = 4;
+// and 1 / LOSS of the way back to ambient, at least a degree a tick
+const LOSS: i32 = 256;
+
+#[wasm_bindgen]
+impl Universe {
+    // one i16 per cell, laid out like cells(), in rough degrees
+    pub fn temps(&self) -> *const i16 {
+        self.temps.as_ptr()
+    }
+
+    // adds delta (negative to cool) to every cell within radius of (x, y),
+    // for tools like a blowtorch or an ice pack. it spreads from there on
+    // the following ticks.
+    pub fn apply_heat_source(&mut self, x: i32, y: i32, radius: i32, delta: i16) {
+        let radius = radius.max(0);
+        for dx in -radius..radius + 1 {
+            for dy in -radius..radius + 1 {
+                let (px, py) = (x + dx, y + dy);
+                if dx * dx + dy * dy > radius * radius
+                    || px < 0
+                    || px > self.width - 1
+                    || py < 0
+                    || py > self.height - 1
+                {
+                    continue;
+                }
+                let i = self.get_index(px, py);
+                self.temps[i] = self.temps[i].saturating_add(delta);
+            }
+        }
+    }
+}
+
+impl Universe {
+    pub fn temps_slice(&self) -> &[i16] {
+        &self.temps
+    }
+
+    // plain diffusion between the four side neighbours, the grid edge
+    // insulated, with a little loss back to ambient everywhere
+    pub(crate) fn heat_pass(&mut self) {
+        let before = self.temps.clone();
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let t = before[self.get_index(x, y)] as i32;
+                let (mut sum, mut n) = (0, 0);
+                for &(dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || nx > self.width - 1 || ny < 0 || ny > self.height - 1 {
+                        continue;
+                    }
+                    sum += before[self.get_index(nx, ny)] as i32;
+                    n += 1;
+                }
+                if n == 0 {
+                    n = 1;
+                    sum = t;
+                }
+                // rounded rather than truncated, so a gap of a couple of
+                // degrees still evens out
+                let mut next = t + div_round(sum - t * n, n * SPREAD);
+                // the loss rounds away from zero, so a cell a few degrees
+                // off still creeps the last of the way back to ambient
+                let off = next - AMBIENT as i32;
+                next -= (off + off.signum() * (LOSS - 1)) / LOSS;
+                let i = self.get_index(x, y);
+                self.temps[i] = next as i16;
+            }
+        }
+    }
+}
+
+// a / b rounded to the nearest whole number, halves away from zero. b > 0.
+fn div_round(a: i32, b: i32) -> i32 {
+    (a + a.signum() * (b / 2)) / b
+}
@@ -5,16 +5,33 @@ extern crate rand_xoshiro;
 extern crate wasm_bindgen;
 extern crate web_sys;
 
+mod font;
+mod heat;
+mod light;
+mod presets;
+mod replay;
 mod species;
 mod utils;
 
+pub use presets::Preset;
+use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
 use rand_xoshiro::SplitMix64;
-use species::Species;
-use std::collections::VecDeque;
+pub use species::Species;
+use std::collections::{HashMap, VecDeque};
+use std::mem;
 use wasm_bindgen::prelude::*;
 // use web_sys::console;
 
+/// Version of the serialized scene layout. Bump it whenever the byte layout
+/// changes; species discriminants are covered by their own stability rule.
+pub const FORMAT_VERSION: u8 = 1;
+
+#[wasm_bindgen]
+pub fn format_version() -> u8 {
+    FORMAT_VERSION
+}
+
 #[wasm_bindgen]
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -25,6 +42,12 @@ pub struct Wind {
     density: u8,
 }
 
+impl Wind {
+    pub fn pressure(&self) -> u8 {
+        self.pressure
+    }
+}
+
 #[wasm_bindgen]
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -47,6 +70,50 @@ impl Cell {
     pub fn update(&self, api: SandApi) {
         self.species.update(*self, api);
     }
+    pub fn species(&self) -> Species {
+        self.species
+    }
+    pub fn ra(&self) -> u8 {
+        self.ra
+    }
+    pub fn rb(&self) -> u8 {
+        self.rb
+    }
+    pub fn is_flammable(&self) -> bool {
+        self.species.is_flammable()
+    }
+    pub fn is_fluid(&self) -> bool {
+        self.species.is_fluid()
+    }
+    pub fn is_powder(&self) -> bool {
+        self.species.is_powder()
+    }
+    pub fn is_solid(&self) -> bool {
+        self.species.is_solid()
+    }
+}
+
+pub const MAX_UNDO: usize = 50;
+
+// rb marker for the cell followed by tag_cell, and how far it may move in
+// one tick and still be found again
+const TAG_ID: u8 = 255;
+const TAG_SEARCH: i32 = 3;
+
+// the most cells glue can hold together and still have the wind move them
+const CLUMP_MAX: usize = 64;
+
+// the order update_pass visits cells in
+#[wasm_bindgen]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanOrder {
+    // column by column, top to bottom, flipping left/right each generation
+    AlternatingColumns = 0,
+    // row by row from the floor up, flipping left/right each generation
+    BottomUp = 1,
+    // every cell once, in a fresh shuffle from the seeded rng each tick
+    Random = 2,
 }
 
 static EMPTY_CELL: Cell = Cell {
@@ -56,16 +123,82 @@ static EMPTY_CELL: Cell = Cell {
     clock: 0,
 };
 
+// still air as far as blow_wind is concerned. every universe starts out
+// under it, since the fluid sim only overwrites winds once it has run and
+// a zeroed field reads as a strong up-left gust until then.
+static CALM_WIND: Wind = Wind {
+    dx: 126,
+    dy: 126,
+    pressure: 0,
+    density: 0,
+};
+
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct Universe {
     width: i32,
     height: i32,
     cells: Vec<Cell>,
     undo_stack: VecDeque<Vec<Cell>>,
+    redo_stack: VecDeque<Vec<Cell>>,
     winds: Vec<Wind>,
     burns: Vec<Wind>,
     generation: u8,
+    // ticks since creation; unlike generation this never wraps in practice
+    total_ticks: u64,
     rng: SplitMix64,
+    nanite_cap: u32,
+    // nanites alive as of the last tick, plus any spawned since
+    nanites: u32,
+    nanite_tally: u32,
+    evaporation: u8,
+    firework_fuse: u8,
+    firework_burst: u8,
+    acid_fumes: bool,
+    tagged: Option<Species>,
+    tag_path: Vec<i32>,
+    paint_randomness: u8,
+    gas_rise: u8,
+    gas_explosive: bool,
+    dust_explosive: bool,
+    acid_resistant: [bool; 256],
+    boundary: Species,
+    geyser_period: u8,
+    egg_hatch: u8,
+    honey_stall: u8,
+    lava_cooling: u8,
+    seed_needs_water: bool,
+    seed_needs_soil: bool,
+    seed_delay: u8,
+    // ticks each waiting seed has sat out so far, by cell index
+    seed_waits: HashMap<usize, u8>,
+    tick_order: ScanOrder,
+    // see set_simulation_scale
+    sim_scale: u8,
+    // species updates run so far this tick, see last_tick_updates
+    updates: u32,
+    last_updates: u32,
+    // cells that were written with something different during the last tick
+    changed: Vec<u8>,
+    // per-cell brightness from light sources, see light.rs
+    light: Vec<u8>,
+    // per-cell temperature, see heat.rs
+    temps: Vec<i16>,
+    // the grid as of the last reset_dirty_bounds
+    dirty_base: Vec<Cell>,
+    // input log kept since start_recording, see replay.rs
+    recording: Option<Vec<u8>>,
+    species_enabled: [bool; 256],
+    population_cap: [u32; 256],
+    // cells of each species, recounted every tick and kept up to date by
+    // SandApi::set_far in between
+    population: [u32; 256],
+    // cells each species has gained over the session, see created_counts.
+    // the ledger nets out the writes of a single update so a cell that just
+    // moves isn't counted as created
+    created: [u32; 256],
+    ledger: [i32; 256],
+    ledger_touched: Vec<u8>,
 }
 
 pub struct SandApi<'a> {
@@ -79,11 +212,16 @@ impl<'a> SandApi<'a> {
         if dx > 2 || dx < -2 || dy > 2 || dy < -2 {
             panic!("oob set");
         }
+        self.get_far(dx, dy)
+    }
+    // get and set without the 2-cell reach limit, for devices that act
+    // along a whole run of cells at once
+    pub fn get_far(&mut self, dx: i32, dy: i32) -> Cell {
         let nx = self.x + dx;
         let ny = self.y + dy;
         if nx < 0 || nx > self.universe.width - 1 || ny < 0 || ny > self.universe.height - 1 {
             return Cell {
-                species: Species::Wall,
+                species: self.universe.boundary,
                 ra: 0,
                 rb: 0,
                 clock: self.universe.generation,
@@ -95,6 +233,9 @@ impl<'a> SandApi<'a> {
         if dx > 2 || dx < -2 || dy > 2 || dy < -2 {
             panic!("oob set");
         }
+        self.set_far(dx, dy, v);
+    }
+    pub fn set_far(&mut self, dx: i32, dy: i32, v: Cell) {
         let nx = self.x + dx;
         let ny = self.y + dy;
 
@@ -102,10 +243,30 @@ impl<'a> SandApi<'a> {
             return;
         }
         let i = self.universe.get_index(nx, ny);
+        let old = self.universe.cells[i];
+        if old.species != v.species || old.ra != v.ra || old.rb != v.rb {
+            self.universe.changed[i] = 1;
+        }
+        if old.species != v.species {
+            let population = &mut self.universe.population;
+            population[old.species as usize] = population[old.species as usize].saturating_sub(1);
+            population[v.species as usize] += 1;
+            let ledger = &mut self.universe.ledger;
+            ledger[old.species as usize] -= 1;
+            ledger[v.species as usize] += 1;
+            let touched = &mut self.universe.ledger_touched;
+            touched.extend_from_slice(&[old.species as u8, v.species as u8]);
+        }
         // v.clock += 1;
         self.universe.cells[i] = v;
         self.universe.cells[i].clock = self.universe.generation.wrapping_add(1);
     }
+    // false while this cell's species is at its population cap; growing
+    // species check it before spreading into a new cell
+    pub fn may_grow(&mut self) -> bool {
+        let s = self.universe.get_cell(self.x, self.y).species as usize;
+        self.universe.population[s] < self.universe.population_cap[s]
+    }
     pub fn get_fluid(&mut self) -> Wind {
         let idx = self.universe.get_index(self.x, self.y);
 
@@ -116,6 +277,18 @@ impl<'a> SandApi<'a> {
 
         self.universe.burns[idx] = v;
     }
+    // like set_fluid, for a cell some way off. the GPU sim reads burns
+    // back in, so this is how a species pushes on wind it doesn't sit in
+    pub fn set_fluid_far(&mut self, dx: i32, dy: i32, v: Wind) {
+        let nx = self.x + dx;
+        let ny = self.y + dy;
+
+        if nx < 0 || nx > self.universe.width - 1 || ny < 0 || ny > self.universe.height - 1 {
+            return;
+        }
+        let idx = self.universe.get_index(nx, ny);
+        self.universe.burns[idx] = v;
+    }
 
     pub fn rand_int(&mut self, n: i32) -> i32 {
         self.universe.rng.gen_range(0..n)
@@ -152,6 +325,54 @@ impl<'a> SandApi<'a> {
         }
     }
 
+    // clears a disc around this cell, bypassing the usual 2-cell reach.
+    // flammables just past the rim catch fire, and the pressure spike is
+    // written into burns so the fluid sim blows outward from the crater.
+    pub fn explode(&mut self, radius: i32) {
+        for dx in -(radius + 1)..(radius + 2) {
+            for dy in -(radius + 1)..(radius + 2) {
+                let d2 = (dx * dx) + (dy * dy);
+                if d2 > (radius + 1) * (radius + 1) {
+                    continue;
+                }
+                let nx = self.x + dx;
+                let ny = self.y + dy;
+                if nx < 0 || nx > self.universe.width - 1 || ny < 0 || ny > self.universe.height - 1
+                {
+                    continue;
+                }
+                let i = self.universe.get_index(nx, ny);
+                let species = self.universe.cells[i].species;
+                if species == Species::Wall || species == Species::Cloner {
+                    continue;
+                }
+                if d2 > radius * radius {
+                    if species.is_flammable() {
+                        let ra = 150 + self.rand_int(50) as u8;
+                        self.set_far(
+                            dx,
+                            dy,
+                            Cell {
+                                species: Species::Fire,
+                                ra,
+                                rb: 0,
+                                clock: 0,
+                            },
+                        );
+                    }
+                    continue;
+                }
+                self.set_far(dx, dy, EMPTY_CELL);
+                self.universe.burns[i] = Wind {
+                    dx: 0,
+                    dy: 0,
+                    pressure: 255 - (d2 * 120 / (radius * radius).max(1)) as u8,
+                    density: 120,
+                };
+            }
+        }
+    }
+
     pub fn rand_vec_8(&mut self) -> (i32, i32) {
         let i = self.rand_int(8);
         match i {
@@ -183,51 +404,64 @@ impl Universe {
         // let js: JsValue = (dx).into();
         // console::log_2(&"dx: ".into(), &js);
 
-        for x in 0..self.width {
-            for y in 0..self.height {
-                let cell = self.get_cell(x, y);
-                let wind = self.get_wind(x, y);
-                Universe::blow_wind(
-                    cell,
-                    wind,
-                    SandApi {
-                        universe: self,
-                        x,
-                        y,
-                    },
-                )
-            }
-        }
-        self.generation = self.generation.wrapping_add(1);
-        for x in 0..self.width {
-            let scanx = if self.generation % 2 == 0 {
-                self.width - (1 + x)
-            } else {
-                x
-            };
+        self.tick_with_hook(|_| {});
+    }
 
-            for y in 0..self.height {
-                let idx = self.get_index(scanx, y);
-                let cell = self.get_cell(scanx, y);
+    // average microseconds per tick over `iterations` ticks
+    pub fn benchmark_tick(&mut self, iterations: u32) -> f64 {
+        if iterations == 0 {
+            return 0.0;
+        }
+        let stopwatch = utils::Stopwatch::start();
+        for _ in 0..iterations {
+            self.tick();
+        }
+        stopwatch.elapsed_micros() / iterations as f64
+    }
 
-                self.burns[idx] = Wind {
-                    dx: 0,
-                    dy: 0,
-                    pressure: 0,
-                    density: 0,
-                };
-                Universe::update_cell(
-                    cell,
-                    SandApi {
-                        universe: self,
-                        x: scanx,
-                        y,
-                    },
-                );
+    // drops powders and liquids straight to rest without running any of
+    // their reactions, for use after bulk edits. stops early once nothing moves.
+    pub fn settle(&mut self, max_iterations: u32) {
+        for _ in 0..max_iterations {
+            let mut moved = false;
+            for x in 0..self.width {
+                for y in (0..self.height).rev() {
+                    let cell = self.get_cell(x, y);
+                    if !cell.is_powder() && !cell.is_fluid() && cell.species != Species::Stone {
+                        continue;
+                    }
+                    let mut ny = y;
+                    while ny + 1 < self.height && self.get_cell(x, ny + 1).species == Species::Empty
+                    {
+                        ny += 1;
+                    }
+                    let mut nx = x;
+                    if ny == y && y + 1 < self.height {
+                        let dx = if self.rng.gen::<bool>() { 1 } else { -1 };
+                        for &dx in [dx, -dx].iter() {
+                            if x + dx >= 0
+                                && x + dx < self.width
+                                && self.get_cell(x + dx, y + 1).species == Species::Empty
+                            {
+                                nx = x + dx;
+                                ny = y + 1;
+                                break;
+                            }
+                        }
+                    }
+                    if nx != x || ny != y {
+                        let from = self.get_index(x, y);
+                        let to = self.get_index(nx, ny);
+                        self.cells[to] = cell;
+                        self.cells[from] = EMPTY_CELL;
+                        moved = true;
+                    }
+                }
+            }
+            if !moved {
+                break;
             }
         }
-
-        self.generation = self.generation.wrapping_add(1);
     }
 
     pub fn width(&self) -> i32 {
@@ -238,6 +472,12 @@ impl Universe {
         self.height
     }
 
+    // how many ticks have run, for timing things longer than the wrapping
+    // generation counter can measure
+    pub fn total_ticks(&self) -> u64 {
+        self.total_ticks
+    }
+
     pub fn cells(&self) -> *const Cell {
         self.cells.as_ptr()
     }
@@ -250,6 +490,7 @@ impl Universe {
         self.burns.as_ptr()
     }
     pub fn paint(&mut self, x: i32, y: i32, size: i32, species: Species) {
+        self.record_paint(x, y, size, species);
         let size = size;
         let radius: f64 = (size as f64) / 2.0;
 
@@ -261,156 +502,1499 @@ impl Universe {
                 if (((dx * dx) + (dy * dy)) as f64) > (radius * radius) {
                     continue;
                 };
-                let px = x + dx;
-                let py = y + dy;
-                let i = self.get_index(px, py);
+                self.paint_cell(x + dx, y + dy, size, species);
+            }
+        }
+    }
 
-                if px < 0 || px > self.width - 1 || py < 0 || py > self.height - 1 {
+    // an oval stamp with separate horizontal and vertical radii. a zero
+    // radius collapses it to a line, both zero to a single cell.
+    pub fn paint_ellipse(&mut self, x: i32, y: i32, rx: i32, ry: i32, species: Species) {
+        let (rx, ry) = (rx.abs(), ry.abs());
+        let size = 2 * rx.max(ry);
+        for dx in -rx..(rx + 1) {
+            for dy in -ry..(ry + 1) {
+                // (dx/rx)^2 + (dy/ry)^2 <= 1, without dividing
+                if dx * dx * ry * ry + dy * dy * rx * rx > rx * rx * ry * ry {
                     continue;
                 }
-                if self.get_cell(px, py).species == Species::Empty || species == Species::Empty {
-                    self.cells[i] = Cell {
-                        species: species,
-                        ra: 60
-                            + (size as u8)
-                            + (self.rng.gen::<f32>() * 30.) as u8
-                            + ((self.generation % 127) as i8 - 60).abs() as u8,
-                        rb: 0,
-                        clock: self.generation,
-                    }
-                }
+                self.paint_cell(x + dx, y + dy, size, species);
             }
         }
     }
 
-    pub fn push_undo(&mut self) {
-        self.undo_stack.push_front(self.cells.clone());
-        self.undo_stack.truncate(50);
+    // stamp an arbitrary row-major mask, `mask_width` cells across and
+    // centered on (x, y); any nonzero entry paints. a trailing partial row
+    // is ignored.
+    pub fn apply_brush_shape(
+        &mut self,
+        x: i32,
+        y: i32,
+        mask_width: i32,
+        mask: &[u8],
+        species: Species,
+    ) {
+        if mask_width <= 0 {
+            return;
+        }
+        let mask_height = mask.len() as i32 / mask_width;
+        let size = mask_width.max(mask_height);
+        let (x0, y0) = (x - mask_width / 2, y - mask_height / 2);
+        for my in 0..mask_height {
+            for mx in 0..mask_width {
+                if mask[(my * mask_width + mx) as usize] != 0 {
+                    self.paint_cell(x0 + mx, y0 + my, size, species);
+                }
+            }
+        }
     }
 
-    pub fn pop_undo(&mut self) {
-        let old_state = self.undo_stack.pop_front();
-        match old_state {
-            Some(state) => self.cells = state,
-            None => (),
-        };
+    // dissolve mineral into the water under a round brush, turning it into
+    // the solution crystal grows from. other cells are left alone.
+    pub fn dissolve(&mut self, x: i32, y: i32, size: i32) {
+        let radius = size / 2;
+        for dx in -radius..(radius + 1) {
+            for dy in -radius..(radius + 1) {
+                let (px, py) = (x + dx, y + dy);
+                if dx * dx + dy * dy > radius * radius
+                    || px < 0
+                    || px > self.width - 1
+                    || py < 0
+                    || py > self.height - 1
+                {
+                    continue;
+                }
+                let i = self.get_index(px, py);
+                if self.cells[i].species == Species::Water {
+                    self.cells[i].rb |= species::SOLUTION;
+                }
+            }
+        }
     }
 
-    pub fn flush_undos(&mut self) {
-        self.undo_stack.clear();
-    }
+    // paint, and give the wind under the brush a push of (dx, dy), in the
+    // same frame as add_wind, so the stamp is flung as it lands
+    #[allow(clippy::too_many_arguments)]
+    pub fn paint_with_velocity(
+        &mut self,
+        x: i32,
+        y: i32,
+        size: i32,
+        species: Species,
+        dx: i8,
+        dy: i8,
+    ) {
+        // goes through paint and add_wind so a recording captures it
+        self.paint(x, y, size, species);
+        let radius: f64 = (size as f64) / 2.0;
 
-    pub fn new(width: i32, height: i32) -> Universe {
-        let cells = (0..width * height).map(|_i| EMPTY_CELL).collect();
-        let winds: Vec<Wind> = (0..width * height)
-            .map(|_i| Wind {
-                dx: 0,
-                dy: 0,
-                pressure: 0,
-                density: 0,
-            })
-            .collect();
+        let floor = (radius + 1.0) as i32;
+        let ciel = (radius + 1.5) as i32;
 
-        let burns: Vec<Wind> = (0..width * height)
-            .map(|_i| Wind {
-                dx: 0,
-                dy: 0,
-                pressure: 0,
-                density: 0,
-            })
-            .collect();
-        let rng: SplitMix64 = SeedableRng::seed_from_u64(0x734f6b89de5f83cc);
-        Universe {
-            width,
-            height,
-            cells,
-            undo_stack: VecDeque::with_capacity(50),
-            burns,
-            winds,
-            generation: 0,
-            rng,
+        for ox in -floor..ciel {
+            for oy in -floor..ciel {
+                if (((ox * ox) + (oy * oy)) as f64) > (radius * radius) {
+                    continue;
+                };
+                self.add_wind(x + ox, y + oy, dx, dy);
+            }
         }
     }
-}
 
-//private methods
-impl Universe {
-    fn get_index(&self, x: i32, y: i32) -> usize {
-        (x * self.height + y) as usize
+    // paint, and also pump up the wind under the brush so the stamp bursts
+    // outward from its centre, like spraying from a pressurised nozzle
+    pub fn paint_pressure(&mut self, x: i32, y: i32, size: i32, species: Species, pressure: u8) {
+        self.paint(x, y, size, species);
+        let radius: f64 = (size as f64) / 2.0 + 1.0;
+        let reach = radius as i32 + 1;
+        let push = pressure as i32 / 2;
+        for dx in -reach..reach + 1 {
+            for dy in -reach..reach + 1 {
+                let (px, py) = (x + dx, y + dy);
+                if ((dx * dx + dy * dy) as f64) > radius * radius
+                    || px < 0
+                    || px > self.width - 1
+                    || py < 0
+                    || py > self.height - 1
+                {
+                    continue;
+                }
+                let i = self.get_index(px, py);
+                let wind = &mut self.winds[i];
+                wind.pressure = wind.pressure.max(pressure);
+                // axes swapped, see blow_wind
+                wind.dy = (wind.dy as i32 + dx.signum() * push).clamp(0, 255) as u8;
+                wind.dx = (wind.dx as i32 + dy.signum() * push).clamp(0, 255) as u8;
+            }
+        }
     }
 
-    fn get_cell(&self, x: i32, y: i32) -> Cell {
-        let i = self.get_index(x, y);
-        return self.cells[i];
+    // fills [x0, x1) x [y0, y1) with a stipple whose density ramps linearly
+    // from start_density at x0 to end_density at x1 (both 0.0 - 1.0)
+    #[allow(clippy::too_many_arguments)]
+    pub fn paint_gradient(
+        &mut self,
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        species: Species,
+        start_density: f32,
+        end_density: f32,
+    ) {
+        let span = ((x1 - x0) as f32 - 1.0).max(1.0);
+        for x in x0.max(0)..x1.min(self.width) {
+            let t = (x - x0) as f32 / span;
+            let density = start_density + (end_density - start_density) * t;
+            for y in y0.max(0)..y1.min(self.height) {
+                if self.rng.gen::<f32>() < density {
+                    self.paint_cell(x, y, 1, species);
+                }
+            }
+        }
     }
 
-    fn get_wind(&self, x: i32, y: i32) -> Wind {
-        let i = self.get_index(x, y);
-        return self.winds[i];
+    // fills [x0, x1) x [y0, y1) wherever smooth value noise (0.0 - 1.0)
+    // beats `threshold`. the noise is random values on a lattice `scale`
+    // cells apart, drawn from the seeded rng and eased in between, so larger
+    // scales give bigger blobs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn paint_noise(
+        &mut self,
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        species: Species,
+        threshold: f32,
+        scale: f32,
+    ) {
+        let scale = scale.max(1.0);
+        let cols = ((x1 - x0).max(0) as f32 / scale) as usize + 2;
+        let rows = ((y1 - y0).max(0) as f32 / scale) as usize + 2;
+        let lattice: Vec<f32> = (0..cols * rows).map(|_| self.rng.gen::<f32>()).collect();
+        let ease = |t: f32| t * t * (3.0 - 2.0 * t);
+        for x in x0.max(0)..x1.min(self.width) {
+            let fx = (x - x0) as f32 / scale;
+            let (cx, tx) = (fx as usize, ease(fx.fract()));
+            for y in y0.max(0)..y1.min(self.height) {
+                let fy = (y - y0) as f32 / scale;
+                let (cy, ty) = (fy as usize, ease(fy.fract()));
+                let at = |c: usize, r: usize| lattice[r * cols + c];
+                let top = at(cx, cy) + (at(cx + 1, cy) - at(cx, cy)) * tx;
+                let bottom = at(cx, cy + 1) + (at(cx + 1, cy + 1) - at(cx, cy + 1)) * tx;
+                if top + (bottom - top) * ty > threshold {
+                    self.paint_cell(x, y, 1, species);
+                }
+            }
+        }
     }
 
-    fn blow_wind(cell: Cell, wind: Wind, mut api: SandApi) {
-        if cell.clock - api.universe.generation == 1 {
-            return;
-        }
-        if cell.species == Species::Empty {
-            return;
+    pub fn touching(&self, x: i32, y: i32, species: Species) -> bool {
+        for dx in -1..2 {
+            for dy in -1..2 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let nx = x + dx;
+                let ny = y + dy;
+                if nx < 0 || nx > self.width - 1 || ny < 0 || ny > self.height - 1 {
+                    continue;
+                }
+                if self.get_cell(nx, ny).species == species {
+                    return true;
+                }
+            }
         }
-        let mut dx = 0;
-        let mut dy = 0;
-
-        let threshold = match cell.species {
-            Species::Empty => 500,
-            Species::Wall => 500,
-            Species::Cloner => 500,
-
-            Species::Stone => 70,
-            Species::Wood => 70,
+        false
+    }
 
-            Species::Plant => 60,
-            Species::Lava => 60,
-            Species::Ice => 60,
+    // scroll everything by (dx, dy). whatever moves off the edge is lost,
+    // and the strip that opens up is painted with `fill` under calm wind at
+    // ambient temperature. temperature and light move with the cells;
+    // dirty_base stays put so dirty_bounds still covers the whole scroll.
+    pub fn shift(&mut self, dx: i32, dy: i32, fill: Species) {
+        let (w, h) = (self.width, self.height);
+        let still = Wind {
+            dx: 0,
+            dy: 0,
+            pressure: 0,
+            density: 0,
+        };
+        let mut cells = vec![EMPTY_CELL; (w * h) as usize];
+        let mut winds = vec![CALM_WIND; (w * h) as usize];
+        let mut burns = vec![still; (w * h) as usize];
+        let mut temps = vec![heat::AMBIENT; (w * h) as usize];
+        let mut light = vec![0; (w * h) as usize];
+        let mut exposed = Vec::new();
+        for x in 0..w {
+            for y in 0..h {
+                let (sx, sy) = (x - dx, y - dy);
+                let i = self.get_index(x, y);
+                if sx < 0 || sx > w - 1 || sy < 0 || sy > h - 1 {
+                    exposed.push((x, y));
+                    continue;
+                }
+                let j = self.get_index(sx, sy);
+                cells[i] = self.cells[j];
+                winds[i] = self.winds[j];
+                burns[i] = self.burns[j];
+                temps[i] = self.temps[j];
+                light[i] = self.light[j];
+            }
+        }
+        let before = mem::replace(&mut self.cells, cells);
+        self.winds = winds;
+        self.burns = burns;
+        self.temps = temps;
+        self.light = light;
+        if fill != Species::Empty {
+            for (x, y) in exposed {
+                self.place_cell(x, y, 1, fill);
+            }
+        }
+        for (i, (now, then)) in self.cells.iter().zip(before.iter()).enumerate() {
+            if now.species != then.species || now.ra != then.ra || now.rb != then.rb {
+                self.changed[i] = 1;
+            }
+        }
+    }
 
-            Species::Fungus => 54,
+    // every a becomes b and every b becomes a, in one pass. ra is kept,
+    // rb is cleared since it means something different to each species.
+    pub fn swap_species(&mut self, a: Species, b: Species) {
+        for cell in self.cells.iter_mut() {
+            if cell.species == a {
+                cell.species = b;
+                cell.rb = 0;
+            } else if cell.species == b {
+                cell.species = a;
+                cell.rb = 0;
+            }
+        }
+    }
 
-            Species::Oil => 50,
+    // every cell of `species` becomes empty, stamped like a set from the
+    // api so nothing else in the current tick treats it as fresh
+    pub fn erase_species(&mut self, species: Species) {
+        let clock = self.generation.wrapping_add(1);
+        for cell in self.cells.iter_mut() {
+            if cell.species == species {
+                *cell = Cell {
+                    clock,
+                    ..EMPTY_CELL
+                };
+            }
+        }
+    }
 
-            // Intentionally left out and covered by the default case
-            // Species::Water => 40,
-            // Species::Acid => 40,
-            Species::Seed => 35,
+    // same size and the same species, ra and rb everywhere; clocks and
+    // generation don't matter
+    pub fn cells_equal(&self, other: &Universe) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self
+                .cells
+                .iter()
+                .zip(other.cells.iter())
+                .all(|(a, b)| a.species == b.species && a.ra == b.ra && a.rb == b.rb)
+    }
 
-            Species::Sand => 30,
-            Species::Mite => 30,
-            Species::Rocket => 30,
+    // an independent copy, rng included, for trying things out without
+    // touching this one
+    pub fn clone_state(&self) -> Universe {
+        self.clone()
+    }
 
-            Species::Dust => 10,
-            Species::Fire => 5,
-            Species::Gas => 5,
-            /*
-             Some hacked species values exist outside of the enum values.
-             Making sure the default case is emitted allows "BELP" to have a defined wind threshold.
-             Originally, threshold was a hardcoded value, so this preserves that original glitch behavior.
-             See: https://sandspiel.club/#eMlYGC52XIto0NM1WjaJ
-            */
-            _ => 40,
-        };
+    // how many cells of `species` are in [x0, x1) x [y0, y1), clamped to the grid
+    pub fn count_in_region(&self, x0: i32, y0: i32, x1: i32, y1: i32, species: Species) -> u32 {
+        let mut n = 0;
+        for x in x0.max(0)..x1.min(self.width) {
+            for y in y0.max(0)..y1.min(self.height) {
+                if self.get_cell(x, y).species == species {
+                    n += 1;
+                }
+            }
+        }
+        n
+    }
 
-        let wx = (wind.dy as i32) - 126;
-        let wy = (wind.dx as i32) - 126;
+    // a pacing hint for the frontend: how many ticks per frame it can
+    // likely afford, from 4 when the last tick barely touched anything down to
+    // 1 when a fifth or more of the grid changed
+    pub fn recommended_substeps(&self) -> u8 {
+        let changed = self.changed.iter().filter(|&&c| c != 0).count();
+        let percent = changed * 100 / self.changed.len().max(1);
+        match percent {
+            0 => 4,
+            1..=4 => 3,
+            5..=19 => 2,
+            _ => 1,
+        }
+    }
 
-        if wx > threshold {
-            dx = 1;
+    // how many cells of `through` can be reached from (x, y) stepping up,
+    // down, left and right only through that species; 0 if (x, y) isn't one
+    pub fn flood_count(&self, x: i32, y: i32, through: Species) -> u32 {
+        if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+            return 0;
         }
-        if wy > threshold {
-            dy = 1;
+        let mut seen = vec![false; self.cells.len()];
+        let mut stack = vec![(x, y)];
+        let mut n = 0;
+        while let Some((x, y)) = stack.pop() {
+            if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+                continue;
+            }
+            let i = self.get_index(x, y);
+            if seen[i] || self.cells[i].species != through {
+                continue;
+            }
+            seen[i] = true;
+            n += 1;
+            stack.extend_from_slice(&[(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
         }
-        if wx < -threshold {
-            dx = -1;
+        n
+    }
+
+    // 1 for cells of `species`, 0 otherwise, row by row from the top left
+    // (note cells() itself is column-major)
+    pub fn species_mask(&self, species: Species) -> Vec<u8> {
+        let mut mask = Vec::with_capacity((self.width * self.height) as usize);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                mask.push((self.get_cell(x, y).species == species) as u8);
+            }
         }
-        if wy < -threshold {
+        mask
+    }
+
+    // 1 for cells that changed species, ra or rb during the last tick, row by
+    // row from the top left like species_mask. a cell that changed and
+    // changed back within the tick still counts.
+    pub fn change_mask(&self) -> Vec<u8> {
+        let mut mask = Vec::with_capacity((self.width * self.height) as usize);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                mask.push(self.changed[self.get_index(x, y)]);
+            }
+        }
+        mask
+    }
+
+    // [x0, y0, x1, y1) around everything change_mask marks, or empty if
+    // the last tick changed nothing
+    pub fn changed_bounds(&self) -> Vec<i32> {
+        let mut bounds: Option<[i32; 4]> = None;
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.changed[self.get_index(x, y)] == 0 {
+                    continue;
+                }
+                let b = bounds.get_or_insert([x, y, x + 1, y + 1]);
+                *b = [b[0].min(x), b[1].min(y), b[2].max(x + 1), b[3].max(y + 1)];
+            }
+        }
+        bounds.map_or(Vec::new(), |b| b.to_vec())
+    }
+
+    // [x0, y0, x1, y1) around every cell whose species, ra or rb differs
+    // from when reset_dirty_bounds was last called, however many ticks and
+    // edits ago that was. empty if nothing differs.
+    pub fn dirty_bounds(&self) -> Vec<i32> {
+        let mut bounds: Option<[i32; 4]> = None;
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let i = self.get_index(x, y);
+                let (now, then) = (self.cells[i], self.dirty_base[i]);
+                if now.species == then.species && now.ra == then.ra && now.rb == then.rb {
+                    continue;
+                }
+                let b = bounds.get_or_insert([x, y, x + 1, y + 1]);
+                *b = [b[0].min(x), b[1].min(y), b[2].max(x + 1), b[3].max(y + 1)];
+            }
+        }
+        bounds.map_or(Vec::new(), |b| b.to_vec())
+    }
+
+    pub fn reset_dirty_bounds(&mut self) {
+        self.dirty_base.clone_from(&self.cells);
+    }
+
+    // per column, the y of the topmost cell that isn't empty or wall, or -1
+    pub fn column_heights(&self) -> Vec<i32> {
+        (0..self.width)
+            .map(|x| {
+                (0..self.height)
+                    .find(|&y| {
+                        let species = self.get_cell(x, y).species;
+                        species != Species::Empty && species != Species::Wall
+                    })
+                    .unwrap_or(-1)
+            })
+            .collect()
+    }
+
+    // hash of species, ra and rb over [x0, x1) x [y0, y1), clamped to the
+    // grid and scanned row by row. clocks are ignored, so two peers can
+    // compare a shared region regardless of when it was last touched.
+    pub fn region_checksum(&self, x0: i32, y0: i32, x1: i32, y1: i32) -> u64 {
+        let (x0, y0) = (x0.max(0), y0.max(0));
+        let (x1, y1) = (x1.min(self.width), y1.min(self.height));
+        let mut hash = utils::FNV_OFFSET;
+        hash = utils::fnv1a(hash, &((x1 - x0).max(0) as u32).to_be_bytes());
+        hash = utils::fnv1a(hash, &((y1 - y0).max(0) as u32).to_be_bytes());
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let cell = self.get_cell(x, y);
+                hash = utils::fnv1a(hash, &[cell.species as u8, cell.ra, cell.rb]);
+            }
+        }
+        hash
+    }
+
+    // writes a region_width x region_height block with its top left at
+    // (x0, y0) from row-major (species, ra, rb) triples, skipping whatever
+    // falls off the grid. an unknown species byte rejects the whole block.
+    pub fn set_cell_region(
+        &mut self,
+        x0: i32,
+        y0: i32,
+        region_width: i32,
+        region_height: i32,
+        data: &[u8],
+    ) -> Result<(), JsValue> {
+        let expected = (region_width.max(0) as usize)
+            .checked_mul(region_height.max(0) as usize)
+            .and_then(|n| n.checked_mul(3));
+        let expected = match expected {
+            Some(n) => n,
+            None => {
+                return Err(JsValue::from_str(&format!(
+                    "region {}x{} is too large",
+                    region_width, region_height
+                )))
+            }
+        };
+        if data.len() != expected {
+            return Err(JsValue::from_str(&format!(
+                "region needs {} bytes but got {}",
+                expected,
+                data.len()
+            )));
+        }
+        if let Some(triple) = data.chunks(3).find(|t| Species::from_u8(t[0]).is_none()) {
+            return Err(JsValue::from_str(&format!(
+                "region has unknown species {}",
+                triple[0]
+            )));
+        }
+        for (n, triple) in data.chunks(3).enumerate() {
+            let (x, y) = (
+                x0.saturating_add(n as i32 % region_width),
+                y0.saturating_add(n as i32 / region_width),
+            );
+            if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+                continue;
+            }
+            let i = self.get_index(x, y);
+            self.cells[i] = Cell {
+                species: Species::from_u8(triple[0]).unwrap(),
+                ra: triple[1],
+                rb: triple[2],
+                clock: self.generation,
+            };
+        }
+        Ok(())
+    }
+
+    // applies an encode_diff that should only touch the tile_size square
+    // tile at (tile_x, tile_y) in tile coordinates. any entry outside it, or
+    // naming an unknown species, rejects the whole diff, leaving the
+    // universe untouched.
+    pub fn apply_region_diff(
+        &mut self,
+        tile_x: i32,
+        tile_y: i32,
+        tile_size: i32,
+        bytes: &[u8],
+    ) -> Result<(), JsValue> {
+        if !bytes.len().is_multiple_of(7) {
+            return Err(JsValue::from_str("diff is truncated"));
+        }
+        let (x0, y0) = (tile_x * tile_size, tile_y * tile_size);
+        let (x1, y1) = (x0 + tile_size, y0 + tile_size);
+        for e in bytes.chunks(7) {
+            let i = u32::from_be_bytes([e[0], e[1], e[2], e[3]]) as usize;
+            let (x, y) = (
+                (i / self.height as usize) as i32,
+                (i % self.height as usize) as i32,
+            );
+            if i >= self.cells.len() || x < x0 || x >= x1 || y < y0 || y >= y1 {
+                return Err(JsValue::from_str(&format!(
+                    "diff writes ({}, {}) outside tile ({}, {})",
+                    x, y, tile_x, tile_y
+                )));
+            }
+            if Species::from_u8(e[4]).is_none() {
+                return Err(JsValue::from_str(&format!(
+                    "diff has unknown species {}",
+                    e[4]
+                )));
+            }
+        }
+        for e in bytes.chunks(7) {
+            let i = u32::from_be_bytes([e[0], e[1], e[2], e[3]]) as usize;
+            self.cells[i] = Cell {
+                species: Species::from_u8(e[4]).unwrap(),
+                ra: e[5],
+                rb: e[6],
+                clock: self.generation,
+            };
+        }
+        Ok(())
+    }
+
+    // layout: version, width and height as big-endian u16s, then runs of
+    // (count, species, ra, rb). clocks are not saved.
+    pub fn to_share_string(&self) -> String {
+        let mut bytes = vec![FORMAT_VERSION];
+        bytes.extend_from_slice(&(self.width as u16).to_be_bytes());
+        bytes.extend_from_slice(&(self.height as u16).to_be_bytes());
+
+        let mut i = 0;
+        while i < self.cells.len() {
+            let cell = self.cells[i];
+            let mut run = 1;
+            while run < 255 && i + run < self.cells.len() {
+                let next = self.cells[i + run];
+                if next.species != cell.species || next.ra != cell.ra || next.rb != cell.rb {
+                    break;
+                }
+                run += 1;
+            }
+            bytes.extend_from_slice(&[run as u8, cell.species as u8, cell.ra, cell.rb]);
+            i += run;
+        }
+        utils::base64_encode(&bytes)
+    }
+
+    pub fn from_share_string(&mut self, s: &str) -> Result<(), JsValue> {
+        let bytes = match utils::base64_decode(s) {
+            Some(bytes) => bytes,
+            None => return Err(JsValue::from_str("share string is not valid base64")),
+        };
+        if bytes.len() < 5 {
+            return Err(JsValue::from_str("share string is truncated"));
+        }
+        if bytes[0] != FORMAT_VERSION {
+            return Err(JsValue::from_str(&format!(
+                "unsupported scene version {}",
+                bytes[0]
+            )));
+        }
+        let width = u16::from_be_bytes([bytes[1], bytes[2]]) as i32;
+        let height = u16::from_be_bytes([bytes[3], bytes[4]]) as i32;
+        if width != self.width || height != self.height {
+            return Err(JsValue::from_str(&format!(
+                "scene is {}x{} but the universe is {}x{}",
+                width, height, self.width, self.height
+            )));
+        }
+
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for run in bytes[5..].chunks(4) {
+            if run.len() < 4 || cells.len() + run[0] as usize > self.cells.len() {
+                return Err(JsValue::from_str("share string is corrupt"));
+            }
+            let species = match Species::from_u8(run[1]) {
+                Some(species) => species,
+                None => {
+                    return Err(JsValue::from_str(&format!(
+                        "share string has unknown species {}",
+                        run[1]
+                    )))
+                }
+            };
+            let cell = Cell {
+                species,
+                ra: run[2],
+                rb: run[3],
+                clock: self.generation,
+            };
+            for _ in 0..run[0] {
+                cells.push(cell);
+            }
+        }
+        if cells.len() != self.cells.len() {
+            return Err(JsValue::from_str("share string is truncated"));
+        }
+        self.cells = cells;
+        Ok(())
+    }
+
+    // nudges the wind at a cell, in the same (dx, dy) frame get_wind_at reads
+    pub fn add_wind(&mut self, x: i32, y: i32, dx: i8, dy: i8) {
+        self.record_wind(x, y, dx, dy);
+        self.nudge_wind(x, y, dx, dy);
+    }
+
+    // swirl the wind around (cx, cy): strongest near the middle, fading to
+    // nothing at `radius`. positive strength turns clockwise on screen.
+    pub fn apply_vortex(&mut self, cx: i32, cy: i32, strength: f32, radius: i32) {
+        // logged as a single entry rather than one add_wind per cell
+        self.record_vortex(cx, cy, strength, radius);
+        self.vortex(cx, cy, strength, radius);
+    }
+
+    // a round gust for blowing with the mouse: the full (dx, dy) in the
+    // middle, falling off linearly to a fraction of it at `radius`
+    pub fn paint_wind_brush(&mut self, x: i32, y: i32, radius: i32, dx: i8, dy: i8) {
+        // logged as a single entry rather than one add_wind per cell
+        self.record_wind_brush(x, y, radius, dx, dy);
+        self.wind_brush(x, y, radius, dx, dy);
+    }
+
+    // the same push everywhere, `strength` along the direction of (dx, dy)
+    pub fn apply_uniform_wind(&mut self, dx: f32, dy: f32, strength: f32) {
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 {
+            return;
+        }
+        let (px, py) = ((dx / len * strength) as i8, (dy / len * strength) as i8);
+        // logged as a single entry rather than one add_wind per cell
+        self.record_uniform_wind(px, py);
+        self.uniform_wind(px, py);
+    }
+
+    pub fn set_nanite_cap(&mut self, cap: u32) {
+        self.nanite_cap = cap;
+    }
+
+    // chance (out of 2048, per tick) that an exposed water cell dries up.
+    // 0 turns evaporation off.
+    pub fn set_evaporation(&mut self, rate: u8) {
+        self.evaporation = rate;
+    }
+
+    // how many cells a firework climbs before bursting, and how many sparks
+    // the burst throws out
+    pub fn set_firework(&mut self, fuse: u8, burst_size: u8) {
+        self.firework_fuse = fuse;
+        self.firework_burst = burst_size;
+    }
+
+    // when on, acid sometimes leaves a puff of gas behind as it eats through things
+    pub fn set_acid_fumes(&mut self, on: bool) {
+        self.acid_fumes = on;
+    }
+
+    // how widely painted ra is jittered; 0 paints everything flat at ra 128
+    pub fn set_paint_randomness(&mut self, amount: u8) {
+        self.paint_randomness = amount;
+    }
+
+    // rise_chance (out of 256) is how often gas drifts straight up instead
+    // of wandering; explosive gas throws a pressure wave when it catches
+    pub fn set_gas_behavior(&mut self, rise_chance: u8, explosive: bool) {
+        self.gas_rise = rise_chance;
+        self.gas_explosive = explosive;
+    }
+
+    // when on, a dense airborne dust cloud touching fire goes up in one go
+    pub fn set_dust_explosive(&mut self, on: bool) {
+        self.dust_explosive = on;
+    }
+
+    // species acid can't dissolve; wall and tar are resistant to begin with
+    pub fn set_acid_resistant(&mut self, species: Species, resistant: bool) {
+        self.acid_resistant[species as usize] = resistant;
+    }
+
+    // take a species out of (or back into) the palette. brushes quietly skip
+    // disabled species; cells already in the grid carry on as normal.
+    pub fn set_species_enabled(&mut self, species: Species, enabled: bool) {
+        self.species_enabled[species as usize] = enabled;
+    }
+
+    // once this many cells of `species` exist it stops spreading, until the
+    // count drops below the cap again. everything else it does, like
+    // burning or falling, carries on. u32::MAX lifts the cap.
+    pub fn set_population_cap(&mut self, species: Species, cap: u32) {
+        self.population_cap[species as usize] = cap;
+    }
+
+    // per species, indexed by its value, how many cells have turned into it
+    // during updates since the universe was made: fire that flares up and
+    // dies a hundred times counts a hundred. it counts cells, so a plant
+    // growing or gas spreading out into a second cell is a creation too.
+    // painting doesn't count, and neither does moving.
+    pub fn created_counts(&self) -> Vec<u32> {
+        self.created.to_vec()
+    }
+
+    // what species sees past the edge of the grid. Wall by default; Empty
+    // lets things fall or flow off and vanish.
+    pub fn set_boundary(&mut self, species: Species) {
+        self.boundary = species;
+    }
+
+    // ticks between geyser eruptions
+    pub fn set_geyser_period(&mut self, ticks: u8) {
+        self.geyser_period = ticks.max(1);
+    }
+
+    // ticks an egg waits before a mite hatches out of it
+    pub fn set_egg_hatch(&mut self, ticks: u8) {
+        self.egg_hatch = ticks.max(1);
+    }
+
+    // chance (out of 256) that honey stays put on a given tick
+    pub fn set_honey_viscosity(&mut self, stall: u8) {
+        self.honey_stall = stall;
+    }
+
+    // how fast lava with nothing hot around it cools; it sets as stone
+    // after 200 / rate ticks on its own. 0 keeps lava molten forever.
+    pub fn set_lava_cooling(&mut self, rate: u8) {
+        self.lava_cooling = rate;
+    }
+
+    // what a landed seed needs before it sprouts: water touching it, sand
+    // (or plant or fungus) under it, and `delay` ticks of both. the
+    // defaults, soil only and no delay, are how seeds always behaved.
+    pub fn set_seed_rules(&mut self, needs_water: bool, needs_soil: bool, delay: u8) {
+        self.seed_needs_water = needs_water;
+        self.seed_needs_soil = needs_soil;
+        self.seed_delay = delay;
+    }
+
+    // how update_pass walks the grid. AlternatingColumns is the original
+    // scan; the others trade its sideways settling bias for their own
+    pub fn set_tick_order(&mut self, order: ScanOrder) {
+        self.tick_order = order;
+    }
+
+    // trades detail for speed under heavy load: at scale n the grid is
+    // updated as n x n blocks. each block runs one update per species in it
+    // and the rest of that species follows it as a lump, so a falling or
+    // flowing mass costs about 1/n^2 as much while moving just as many
+    // cells per tick. the inside of a lump that stays put isn't updated at
+    // all, and lumps move less evenly than single cells. wind still reaches
+    // every cell. 1 (or 0) is the normal full-resolution tick; anything
+    // above 4 is treated as 4.
+    pub fn set_simulation_scale(&mut self, scale: u8) {
+        self.sim_scale = scale.clamp(1, 4);
+    }
+
+    // species updates the last tick ran, counting a lump that followed its
+    // lead cell at a coarse simulation scale as the one update it took
+    pub fn last_tick_updates(&self) -> u32 {
+        self.last_updates
+    }
+
+    // point a pump, laser or stick of dynamite at (x, y) along (dx, dy)
+    pub fn aim(&mut self, x: i32, y: i32, dx: i32, dy: i32) {
+        if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+            return;
+        }
+        let i = self.get_index(x, y);
+        let cell = &mut self.cells[i];
+        if cell.species == Species::Pump || cell.species == Species::Laser {
+            cell.rb = 100 + utils::join_dy_dx(dx.signum(), dy.signum());
+        } else if cell.species == Species::Dynamite && cell.rb < species::DYNAMITE_LIT {
+            // only while unlit; a burning fuse keeps its direction
+            cell.rb = 100 + utils::join_dy_dx(dx.signum(), dy.signum());
+        }
+    }
+
+    // tilt a mirror at (x, y): false is "/", true is "\"
+    pub fn set_mirror(&mut self, x: i32, y: i32, backslash: bool) {
+        if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+            return;
+        }
+        let i = self.get_index(x, y);
+        if self.cells[i].species == Species::Mirror {
+            self.cells[i].rb = backslash as u8;
+        }
+    }
+
+    // set which way the belt through (x, y) runs, the whole straight run of
+    // conveyor cells at once: right for positive dx, left for negative
+    pub fn set_conveyor(&mut self, x: i32, y: i32, dx: i32) {
+        if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+            return;
+        }
+        if dx == 0 || self.get_cell(x, y).species != Species::Conveyor {
+            return;
+        }
+        let rb = (dx < 0) as u8;
+        let mut x0 = x;
+        while x0 > 0 && self.get_cell(x0 - 1, y).species == Species::Conveyor {
+            x0 -= 1;
+        }
+        let mut bx = x0;
+        while bx < self.width && self.get_cell(bx, y).species == Species::Conveyor {
+            let i = self.get_index(bx, y);
+            self.cells[i].rb = rb;
+            bx += 1;
+        }
+    }
+
+    // mark a cell so its position is recorded every tick. tagging an empty
+    // cell (or out of bounds) stops tracking.
+    pub fn tag_cell(&mut self, x: i32, y: i32) {
+        self.tagged = None;
+        self.tag_path.clear();
+        if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+            return;
+        }
+        let i = self.get_index(x, y);
+        if self.cells[i].species == Species::Empty {
+            return;
+        }
+        self.cells[i].rb = TAG_ID;
+        self.tagged = Some(self.cells[i].species);
+        self.tag_path.extend_from_slice(&[x, y]);
+    }
+
+    // flat x, y pairs, oldest first. recording stops if the tagged cell is lost.
+    pub fn tagged_path(&self) -> Vec<i32> {
+        self.tag_path.clone()
+    }
+
+    pub fn push_undo(&mut self) {
+        self.undo_stack.push_front(self.cells.clone());
+        self.undo_stack.truncate(MAX_UNDO);
+        self.redo_stack.clear();
+    }
+
+    pub fn pop_undo(&mut self) {
+        if let Some(state) = self.undo_stack.pop_front() {
+            let current = mem::replace(&mut self.cells, state);
+            self.redo_stack.push_front(current);
+            self.redo_stack.truncate(MAX_UNDO);
+        }
+    }
+
+    pub fn pop_redo(&mut self) {
+        if let Some(state) = self.redo_stack.pop_front() {
+            let current = mem::replace(&mut self.cells, state);
+            self.undo_stack.push_front(current);
+            self.undo_stack.truncate(MAX_UNDO);
+        }
+    }
+
+    pub fn flush_undos(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    // thin the undo history out to at most `max_entries` snapshots, keeping
+    // the oldest and the newest and an even spread of the ones in between,
+    // so each undo step covers more ground. redo is left alone.
+    pub fn compact_undo(&mut self, max_entries: usize) {
+        let len = self.undo_stack.len();
+        if len <= max_entries {
+            return;
+        }
+        if max_entries < 2 {
+            // the oldest is the one that undoes everything
+            let oldest = self.undo_stack.pop_back();
+            self.undo_stack.clear();
+            self.undo_stack.extend(oldest.into_iter().take(max_entries));
+            return;
+        }
+        let keep: Vec<usize> = (0..max_entries)
+            .map(|k| k * (len - 1) / (max_entries - 1))
+            .collect();
+        let old = mem::take(&mut self.undo_stack);
+        self.undo_stack = old
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| keep.contains(i))
+            .map(|(_, snapshot)| snapshot)
+            .collect();
+    }
+
+    // rough heap bytes held by the grids, the per-cell side buffers, the
+    // undo/redo history and any recording in progress
+    pub fn memory_usage(&self) -> usize {
+        let history = |stack: &VecDeque<Vec<Cell>>| {
+            stack.capacity() * mem::size_of::<Vec<Cell>>()
+                + stack
+                    .iter()
+                    .map(|s| s.capacity() * mem::size_of::<Cell>())
+                    .sum::<usize>()
+        };
+        (self.cells.capacity() + self.dirty_base.capacity()) * mem::size_of::<Cell>()
+            + (self.winds.capacity() + self.burns.capacity()) * mem::size_of::<Wind>()
+            + self.changed.capacity()
+            + self.light.capacity()
+            + self.temps.capacity() * mem::size_of::<i16>()
+            + history(&self.undo_stack)
+            + history(&self.redo_stack)
+            + self.tag_path.capacity() * mem::size_of::<i32>()
+            + self.recording.as_ref().map_or(0, |log| log.capacity())
+            + self.ledger_touched.capacity()
+            + self.seed_waits.capacity() * mem::size_of::<(usize, u8)>()
+    }
+
+    pub fn undo_depth(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    pub fn redo_depth(&self) -> usize {
+        self.redo_stack.len()
+    }
+
+    pub fn new(width: i32, height: i32) -> Universe {
+        let cells = (0..width * height).map(|_i| EMPTY_CELL).collect();
+        let winds: Vec<Wind> = (0..width * height).map(|_i| CALM_WIND).collect();
+
+        let burns: Vec<Wind> = (0..width * height)
+            .map(|_i| Wind {
+                dx: 0,
+                dy: 0,
+                pressure: 0,
+                density: 0,
+            })
+            .collect();
+        let rng: SplitMix64 = SeedableRng::seed_from_u64(0x734f6b89de5f83cc);
+        let mut acid_resistant = [false; 256];
+        acid_resistant[Species::Wall as usize] = true;
+        acid_resistant[Species::Tar as usize] = true;
+        Universe {
+            width,
+            height,
+            cells,
+            undo_stack: VecDeque::with_capacity(MAX_UNDO),
+            redo_stack: VecDeque::new(),
+            burns,
+            winds,
+            generation: 0,
+            total_ticks: 0,
+            rng,
+            nanite_cap: 1000,
+            nanites: 0,
+            nanite_tally: 0,
+            evaporation: 0,
+            firework_fuse: 30,
+            firework_burst: 24,
+            acid_fumes: false,
+            tagged: None,
+            tag_path: Vec::new(),
+            paint_randomness: 30,
+            gas_rise: 0,
+            gas_explosive: true,
+            dust_explosive: false,
+            acid_resistant,
+            boundary: Species::Wall,
+            geyser_period: 120,
+            egg_hatch: 100,
+            honey_stall: 200,
+            lava_cooling: 0,
+            seed_needs_water: false,
+            seed_needs_soil: true,
+            seed_delay: 0,
+            seed_waits: HashMap::new(),
+            tick_order: ScanOrder::AlternatingColumns,
+            sim_scale: 1,
+            updates: 0,
+            last_updates: 0,
+            changed: vec![0; (width * height) as usize],
+            light: vec![0; (width * height) as usize],
+            temps: vec![heat::AMBIENT; (width * height) as usize],
+            dirty_base: vec![EMPTY_CELL; (width * height) as usize],
+            recording: None,
+            species_enabled: [true; 256],
+            population_cap: [u32::MAX; 256],
+            population: [0; 256],
+            created: [0; 256],
+            ledger: [0; 256],
+            ledger_touched: Vec::new(),
+        }
+    }
+
+    // a fresh universe with sand and seeds sprinkled in, each cell rolling
+    // against the densities (0.0 to 1.0) on the seeded rng. new() itself
+    // starts empty, i.e. both densities at 0.
+    pub fn new_with_density(
+        width: i32,
+        height: i32,
+        sand_density: f32,
+        seed_density: f32,
+    ) -> Universe {
+        let mut universe = Universe::new(width, height);
+        for x in 0..width {
+            for y in 0..height {
+                let roll = universe.rng.gen::<f32>();
+                if roll < seed_density {
+                    universe.paint_cell(x, y, 1, Species::Seed);
+                } else if roll < seed_density + sand_density {
+                    universe.paint_cell(x, y, 1, Species::Sand);
+                }
+            }
+        }
+        universe
+    }
+}
+
+impl Universe {
+    // find the tagged cell near where it was last seen, nearest match wins
+    fn follow_tag(&mut self) {
+        let species = match self.tagged {
+            Some(species) => species,
+            None => return,
+        };
+        let n = self.tag_path.len();
+        let (lx, ly) = (self.tag_path[n - 2], self.tag_path[n - 1]);
+        let mut best = None;
+        let mut best_d = i32::MAX;
+        for x in (lx - TAG_SEARCH).max(0)..(lx + TAG_SEARCH + 1).min(self.width) {
+            for y in (ly - TAG_SEARCH).max(0)..(ly + TAG_SEARCH + 1).min(self.height) {
+                let cell = self.get_cell(x, y);
+                if cell.species != species || cell.rb != TAG_ID {
+                    continue;
+                }
+                let d = (x - lx) * (x - lx) + (y - ly) * (y - ly);
+                if d < best_d {
+                    best = Some((x, y));
+                    best_d = d;
+                }
+            }
+        }
+        match best {
+            Some((x, y)) => self.tag_path.extend_from_slice(&[x, y]),
+            None => self.tagged = None,
+        }
+    }
+}
+
+// helpers for rust callers, returning types wasm_bindgen can't carry
+impl Universe {
+    // decoded wind at a cell as [dx, dy], zero when out of bounds
+    pub fn get_wind_at(&self, x: i32, y: i32) -> [i8; 2] {
+        if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+            return [0, 0];
+        }
+        let wind = self.get_wind(x, y);
+        let decode = |v: u8| (v as i32 - 126).clamp(-128, 127) as i8;
+        [decode(wind.dy), decode(wind.dx)]
+    }
+
+    // tick, calling `hook` after the wind pass and again after the update
+    // pass, so tests and tools can look at the state in between. tick()
+    // itself goes through here with a hook that does nothing.
+    pub fn tick_with_hook<F: FnMut(&Universe)>(&mut self, mut hook: F) {
+        self.record_tick();
+        self.wind_pass();
+        hook(self);
+        self.update_pass();
+        self.light_pass();
+        self.heat_pass();
+        hook(self);
+    }
+
+    // the grid as a slice, for host code that doesn't want a raw pointer
+    pub fn cells_slice(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn burns_slice(&self) -> &[Wind] {
+        &self.burns
+    }
+
+    // add_wind without the recording, for calls that log themselves
+    pub(crate) fn nudge_wind(&mut self, x: i32, y: i32, dx: i8, dy: i8) {
+        if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+            return;
+        }
+        let i = self.get_index(x, y);
+        let wind = &mut self.winds[i];
+        // the fluid sim stores the axes swapped, see blow_wind
+        wind.dy = (wind.dy as i32 + dx as i32).clamp(0, 255) as u8;
+        wind.dx = (wind.dx as i32 + dy as i32).clamp(0, 255) as u8;
+    }
+
+    // paint_wind_brush without the recording, shared with replay
+    pub(crate) fn wind_brush(&mut self, x: i32, y: i32, radius: i32, dx: i8, dy: i8) {
+        let radius = radius.max(0);
+        for bx in -radius..radius + 1 {
+            for by in -radius..radius + 1 {
+                let d = ((bx * bx + by * by) as f32).sqrt();
+                if d > radius as f32 {
+                    continue;
+                }
+                let falloff = 1.0 - d / (radius + 1) as f32;
+                let (gx, gy) = ((dx as f32 * falloff) as i8, (dy as f32 * falloff) as i8);
+                self.nudge_wind(x + bx, y + by, gx, gy);
+            }
+        }
+    }
+
+    // apply_vortex without the recording, shared with replay
+    pub(crate) fn vortex(&mut self, cx: i32, cy: i32, strength: f32, radius: i32) {
+        for x in (cx - radius).max(0)..(cx + radius + 1).min(self.width) {
+            for y in (cy - radius).max(0)..(cy + radius + 1).min(self.height) {
+                let (rx, ry) = ((x - cx) as f32, (y - cy) as f32);
+                let d = (rx * rx + ry * ry).sqrt();
+                if d == 0.0 || d > radius as f32 {
+                    continue;
+                }
+                let push = strength * (1.0 - d / radius as f32) / d;
+                self.nudge_wind(x, y, (-ry * push) as i8, (rx * push) as i8);
+            }
+        }
+    }
+
+    // apply_uniform_wind once its push is worked out, shared with replay
+    pub(crate) fn uniform_wind(&mut self, dx: i8, dy: i8) {
+        for x in 0..self.width {
+            for y in 0..self.height {
+                self.nudge_wind(x, y, dx, dy);
+            }
+        }
+    }
+
+    pub fn light_slice(&self) -> &[u8] {
+        &self.light
+    }
+
+    // first cell of `species` scanning row by row from the top left
+    pub fn find_species(&self, species: Species) -> Option<[i32; 2]> {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.get_cell(x, y).species == species {
+                    return Some([x, y]);
+                }
+            }
+        }
+        None
+    }
+
+    // walks a Bresenham line from (x0, y0) towards (x1, y1) and returns the
+    // first cell on it that isn't empty, not counting the start. None if the
+    // line gets to its end, or off the grid, without hitting anything.
+    pub fn query_line(&self, x0: i32, y0: i32, x1: i32, y1: i32) -> Option<[i32; 2]> {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y) = (x0, y0);
+        let mut err = dx + dy;
+        while x != x1 || y != y1 {
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+            if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+                return None;
+            }
+            if self.get_cell(x, y).species != Species::Empty {
+                return Some([x, y]);
+            }
+        }
+        None
+    }
+
+    // the cells that differ from an earlier snapshot of the same size, as
+    // entries of (index as a big-endian u32, species, ra, rb). clocks are
+    // not compared.
+    pub fn encode_diff(&self, previous: &[Cell]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (i, (before, after)) in previous.iter().zip(self.cells.iter()).enumerate() {
+            if before.species != after.species || before.ra != after.ra || before.rb != after.rb {
+                bytes.extend_from_slice(&(i as u32).to_be_bytes());
+                bytes.extend_from_slice(&[after.species as u8, after.ra, after.rb]);
+            }
+        }
+        bytes
+    }
+
+    // [created, destroyed, moved or changed] against an earlier snapshot of
+    // the same size. a cell that vanished and reappeared elsewhere as the
+    // same species is counted once as moved rather than as both.
+    pub fn diff_stats(&self, previous: &[Cell]) -> [u32; 3] {
+        let mut created = [0u32; 256];
+        let mut destroyed = [0u32; 256];
+        let mut changed = 0;
+        for (before, after) in previous.iter().zip(self.cells.iter()) {
+            match (before.species, after.species) {
+                (a, b) if a == b => {}
+                (a, Species::Empty) => destroyed[a as usize] += 1,
+                (Species::Empty, b) => created[b as usize] += 1,
+                _ => changed += 1,
+            }
+        }
+        let mut stats = [0, 0, changed];
+        for s in 0..256 {
+            let moved = created[s].min(destroyed[s]);
+            stats[0] += created[s] - moved;
+            stats[1] += destroyed[s] - moved;
+            stats[2] += moved;
+        }
+        stats
+    }
+}
+
+//private methods
+impl Universe {
+    // moves cells the wind is strong enough to carry
+    fn wind_pass(&mut self) {
+        for c in self.changed.iter_mut() {
+            *c = 0;
+        }
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let cell = self.get_cell(x, y);
+                let wind = self.get_wind(x, y);
+                Universe::blow_wind(
+                    cell,
+                    wind,
+                    SandApi {
+                        universe: self,
+                        x,
+                        y,
+                    },
+                )
+            }
+        }
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    // runs every cell's species update, then follows the tagged cell
+    fn update_pass(&mut self) {
+        self.nanites = self.nanite_tally;
+        self.nanite_tally = 0;
+        self.population = [0; 256];
+        for cell in self.cells.iter() {
+            self.population[cell.species as usize] += 1;
+        }
+        // the wind pass only moves cells
+        self.settle_ledger(false);
+        // cleared up front rather than per cell, so a species can write
+        // burns for cells later in the scan (see SandApi::explode)
+        for burn in self.burns.iter_mut() {
+            *burn = Wind {
+                dx: 0,
+                dy: 0,
+                pressure: 0,
+                density: 0,
+            };
+        }
+        self.updates = 0;
+        let flip = self.generation.is_multiple_of(2);
+        // block origins; at scale 1 every cell is its own block
+        let n = self.sim_scale as i32;
+        let mut xs: Vec<i32> = (0..self.width).step_by(n as usize).collect();
+        if flip {
+            xs.reverse();
+        }
+        match self.tick_order {
+            ScanOrder::AlternatingColumns => {
+                for &x in xs.iter() {
+                    for y in (0..self.height).step_by(n as usize) {
+                        self.update_block(x, y);
+                    }
+                }
+            }
+            ScanOrder::BottomUp => {
+                let top = (self.height - 1) / n * n;
+                for y in (0..top + 1).rev().step_by(n as usize) {
+                    for &x in xs.iter() {
+                        self.update_block(x, y);
+                    }
+                }
+            }
+            ScanOrder::Random => {
+                let h = self.height;
+                let mut order: Vec<i32> = (0..self.width * h)
+                    .filter(|i| (i / h) % n == 0 && (i % h) % n == 0)
+                    .collect();
+                order.shuffle(&mut self.rng);
+                for i in order {
+                    self.update_block(i / h, i % h);
+                }
+            }
+        }
+        self.last_updates = self.updates;
+
+        self.generation = self.generation.wrapping_add(1);
+        self.total_ticks += 1;
+        self.follow_tag();
+    }
+
+    // one brush stamp, skipped for species left out of the palette
+    fn paint_cell(&mut self, px: i32, py: i32, size: i32, species: Species) {
+        if self.species_enabled[species as usize] {
+            self.place_cell(px, py, size, species);
+        }
+    }
+
+    // only overwrites empty cells (unless erasing), with the same ra jitter
+    // that paint has always used (at the default randomness)
+    fn place_cell(&mut self, px: i32, py: i32, size: i32, species: Species) {
+        if px < 0 || px > self.width - 1 || py < 0 || py > self.height - 1 {
+            return;
+        }
+        let i = self.get_index(px, py);
+        if self.cells[i].species == Species::Empty || species == Species::Empty {
+            let ra = if self.paint_randomness == 0 {
+                128
+            } else {
+                60 + (size as u8)
+                    + (self.rng.gen::<f32>() * self.paint_randomness as f32) as u8
+                    + ((self.generation % 127) as i8 - 60).abs() as u8
+            };
+            self.cells[i] = Cell {
+                species: species,
+                ra,
+                rb: 0,
+                clock: self.generation,
+            }
+        }
+    }
+
+    // place_cell over [x0, x1) x [y0, y1), clamped to the grid. scenes are
+    // built with this, so they aren't limited by the palette
+    fn fill_rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, species: Species) {
+        for x in x0.max(0)..x1.min(self.width) {
+            for y in y0.max(0)..y1.min(self.height) {
+                self.place_cell(x, y, 1, species);
+            }
+        }
+    }
+
+    fn get_index(&self, x: i32, y: i32) -> usize {
+        (x * self.height + y) as usize
+    }
+
+    fn get_cell(&self, x: i32, y: i32) -> Cell {
+        let i = self.get_index(x, y);
+        return self.cells[i];
+    }
+
+    fn get_wind(&self, x: i32, y: i32) -> Wind {
+        let i = self.get_index(x, y);
+        return self.winds[i];
+    }
+
+    fn blow_wind(cell: Cell, wind: Wind, mut api: SandApi) {
+        if cell.clock.wrapping_sub(api.universe.generation) == 1 {
+            return;
+        }
+        if cell.species == Species::Empty {
+            return;
+        }
+        let mut dx = 0;
+        let mut dy = 0;
+
+        let threshold = match cell.species {
+            Species::Empty => 500,
+            Species::Wall => 500,
+            Species::Membrane => 500,
+            Species::Light => 500,
+            Species::Conveyor => 500,
+            Species::Battery => 500,
+            Species::Cloner => 500,
+            Species::Pump => 500,
+            Species::Laser => 500,
+            Species::Dynamite => 500,
+            Species::Mirror => 500,
+            Species::Geyser => 500,
+            Species::Electric => 500,
+            Species::Charge => 500,
+
+            Species::Stone => 70,
+            Species::Needle => 70,
+            Species::SlakedLime => 70,
+            Species::Radioactive => 70,
+            Species::Antimatter => 70,
+            Species::Crystal => 70,
+            Species::Frost => 70,
+            Species::Rubber => 70,
+            Species::Wood => 70,
+            Species::Bomb => 70,
+            Species::Coral => 70,
+
+            Species::Plant => 60,
+            Species::Vine => 60,
+            Species::Grass => 60,
+            Species::Mycelium => 60,
+            Species::Nanite => 60,
+            Species::Lava => 60,
+            Species::Ice => 60,
+
+            Species::Fungus => 54,
+            Species::Moss => 54,
+            Species::Slime => 55,
+            Species::Honey => 55,
+            Species::Tar => 55,
+
+            Species::Glue if cell.rb == species::GLUE_SET => 70,
+            Species::Oil => 50,
+            Species::Glue => 50,
+            Species::Clay => 45,
+
+            // Intentionally left out and covered by the default case
+            // Species::Water => 40,
+            // Species::Acid => 40,
+            Species::Seed => 35,
+
+            Species::Sand => 30,
+            Species::Soil => 30,
+            Species::Dirt => 30,
+            Species::Quicklime => 30,
+            Species::Phosphorus => 30,
+            Species::Mite => 30,
+            Species::Egg => 30,
+            Species::Rocket => 30,
+            Species::Firework => 30,
+
+            Species::Dust => 10,
+            Species::Balloon => 10,
+            Species::Leaf => 10,
+            Species::Sawdust if cell.rb == 0 => 10,
+            Species::Sawdust => 30,
+            Species::Web => 20,
+            Species::Fire => 5,
+            Species::Gas => 5,
+            Species::Smoke => 5,
+            Species::Confetti => 5,
+            Species::Spore => 5,
+            Species::Bubble => 5,
+            /*
+             Some hacked species values exist outside of the enum values.
+             Making sure the default case is emitted allows "BELP" to have a defined wind threshold.
+             Originally, threshold was a hardcoded value, so this preserves that original glitch behavior.
+             See: https://sandspiel.club/#eMlYGC52XIto0NM1WjaJ
+            */
+            _ => 40,
+        };
+
+        let wx = (wind.dy as i32) - 126;
+        let wy = (wind.dx as i32) - 126;
+
+        if wx > threshold {
+            dx = 1;
+        }
+        if wy > threshold {
+            dy = 1;
+        }
+        if wx < -threshold {
+            dx = -1;
+        }
+        if wy < -threshold {
             dy = -1;
         }
+        if (dx != 0 || dy != 0) && api.universe.is_bonded(api.x, api.y) {
+            api.universe.move_clump(api.x, api.y, dx, dy);
+            return;
+        }
         if (dx != 0 || dy != 0) && api.get(dx, dy).species == Species::Empty {
             api.set(0, 0, EMPTY_CELL);
             if dy == -1
@@ -430,8 +2014,239 @@ impl Universe {
             return;
         }
     }
+    fn is_set_glue(&self, x: i32, y: i32) -> bool {
+        if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+            return false;
+        }
+        let cell = self.get_cell(x, y);
+        cell.species == Species::Glue && cell.rb == species::GLUE_SET
+    }
+
+    // is this cell set glue, or stuck to some?
+    fn is_bonded(&self, x: i32, y: i32) -> bool {
+        let species = self.get_cell(x, y).species;
+        if species == Species::Empty || species == Species::Wall || species == Species::Cloner {
+            return false;
+        }
+        self.is_set_glue(x, y)
+            || [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .iter()
+                .any(|&(dx, dy)| self.is_set_glue(x + dx, y + dy))
+    }
+
+    // moves everything glued to (x, y) one step along (dx, dy) together, if
+    // every cell has room to go. clumps over CLUMP_MAX stay put.
+    fn move_clump(&mut self, x: i32, y: i32, dx: i32, dy: i32) {
+        let mut members = vec![(x, y)];
+        let mut n = 0;
+        while n < members.len() {
+            let (mx, my) = members[n];
+            n += 1;
+            for &(sx, sy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+                let (nx, ny) = (mx + sx, my + sy);
+                if nx < 0 || nx > self.width - 1 || ny < 0 || ny > self.height - 1 {
+                    continue;
+                }
+                let species = self.get_cell(nx, ny).species;
+                if species == Species::Empty
+                    || species == Species::Wall
+                    || species == Species::Cloner
+                    || !(self.is_set_glue(mx, my) || self.is_set_glue(nx, ny))
+                    || members.contains(&(nx, ny))
+                {
+                    continue;
+                }
+                members.push((nx, ny));
+                if members.len() > CLUMP_MAX {
+                    return;
+                }
+            }
+        }
+        let blocked = members.iter().any(|&(mx, my)| {
+            let (tx, ty) = (mx + dx, my + dy);
+            tx < 0
+                || tx > self.width - 1
+                || ty < 0
+                || ty > self.height - 1
+                || (self.get_cell(tx, ty).species != Species::Empty && !members.contains(&(tx, ty)))
+        });
+        if blocked {
+            return;
+        }
+        let clock = self.generation.wrapping_add(1);
+        let moving: Vec<Cell> = members
+            .iter()
+            .map(|&(mx, my)| self.get_cell(mx, my))
+            .collect();
+        for &(mx, my) in members.iter() {
+            let i = self.get_index(mx, my);
+            self.cells[i] = Cell {
+                clock,
+                ..EMPTY_CELL
+            };
+            self.changed[i] = 1;
+        }
+        for (&(mx, my), &cell) in members.iter().zip(moving.iter()) {
+            let i = self.get_index(mx + dx, my + dy);
+            self.cells[i] = Cell { clock, ..cell };
+            self.changed[i] = 1;
+        }
+    }
+
+    // the n x n block at (x0, y0), see set_simulation_scale. for each
+    // species in it, the lowest cell leads; if it stays put, the next cell on
+    // an open edge gets a go. once one moves, the rest of that species in the
+    // block takes the same step where it can.
+    fn update_block(&mut self, x0: i32, y0: i32) {
+        let n = self.sim_scale as i32;
+        if n == 1 {
+            self.update_at(x0, y0);
+            return;
+        }
+        let fresh = self.generation.wrapping_add(1);
+        let (x1, y1) = ((x0 + n).min(self.width), (y0 + n).min(self.height));
+        let mut lump: Vec<(i32, i32)> = Vec::new();
+        let mut done: Vec<Species> = Vec::new();
+        for y in (y0..y1).rev() {
+            for x in x0..x1 {
+                let species = self.get_cell(x, y).species;
+                if species == Species::Empty || done.contains(&species) {
+                    continue;
+                }
+                done.push(species);
+                lump.clear();
+                for ly in (y0..y1).rev() {
+                    for lx in x0..x1 {
+                        let cell = self.get_cell(lx, ly);
+                        if cell.species == species && cell.clock != fresh {
+                            lump.push((lx, ly));
+                        }
+                    }
+                }
+                let mut step = None;
+                let mut tried = 0;
+                while step.is_none() && tried < lump.len() {
+                    let (lx, ly) = lump[tried];
+                    tried += 1;
+                    if tried > 1 && !self.touches_empty(lx, ly) {
+                        continue;
+                    }
+                    self.update_at(lx, ly);
+                    step = self.step_taken(lx, ly, species);
+                }
+                if let Some((dx, dy)) = step {
+                    let mut rest = lump.split_off(tried);
+                    self.follow(&mut rest, dx, dy);
+                }
+            }
+        }
+    }
+
+    // steps each cell of a lump by (dx, dy) after its lead, leading edge
+    // first so nothing steps into a cell the lump still holds. a cell that's
+    // blocked gets its own update instead.
+    fn follow(&mut self, lump: &mut [(i32, i32)], dx: i32, dy: i32) {
+        let fresh = self.generation.wrapping_add(1);
+        lump.sort_by_key(|&(x, y)| -(x * dx + y * dy));
+        for &(x, y) in lump.iter() {
+            let cell = self.get_cell(x, y);
+            if cell.clock == fresh {
+                continue;
+            }
+            let (tx, ty) = (x + dx, y + dy);
+            if tx < 0
+                || tx > self.width - 1
+                || ty < 0
+                || ty > self.height - 1
+                || self.get_cell(tx, ty).species != Species::Empty
+            {
+                self.update_at(x, y);
+                continue;
+            }
+            let mut api = SandApi {
+                universe: self,
+                x,
+                y,
+            };
+            api.set_far(dx, dy, cell);
+            api.set_far(0, 0, EMPTY_CELL);
+            self.settle_ledger(true);
+        }
+    }
+
+    fn touches_empty(&self, x: i32, y: i32) -> bool {
+        for nx in (x - 1).max(0)..(x + 2).min(self.width) {
+            for ny in (y - 1).max(0)..(y + 2).min(self.height) {
+                if self.get_cell(nx, ny).species == Species::Empty {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // where a lead cell of `species` that was at (x, y) went in its update:
+    // the nearest cell it could have stepped to that now holds that species
+    // fresh, provided (x, y) itself no longer does
+    fn step_taken(&self, x: i32, y: i32, species: Species) -> Option<(i32, i32)> {
+        if self.get_cell(x, y).species == species {
+            return None;
+        }
+        let fresh = self.generation.wrapping_add(1);
+        let mut best: Option<(i32, i32)> = None;
+        for dx in -2..3 {
+            for dy in -2..3 {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || nx > self.width - 1 || ny < 0 || ny > self.height - 1 {
+                    continue;
+                }
+                let cell = self.get_cell(nx, ny);
+                if cell.species != species || cell.clock != fresh {
+                    continue;
+                }
+                let closer = match best {
+                    Some((bx, by)) => dx * dx + dy * dy < bx * bx + by * by,
+                    None => true,
+                };
+                if closer {
+                    best = Some((dx, dy));
+                }
+            }
+        }
+        best
+    }
+
+    fn update_at(&mut self, x: i32, y: i32) {
+        let cell = self.get_cell(x, y);
+        if cell.species != Species::Empty {
+            self.updates += 1;
+        }
+        Universe::update_cell(
+            cell,
+            SandApi {
+                universe: self,
+                x,
+                y,
+            },
+        );
+        self.settle_ledger(true);
+    }
+
+    // clears what the last update wrote, crediting any species that came
+    // out ahead to created
+    fn settle_ledger(&mut self, credit: bool) {
+        for &s in self.ledger_touched.iter() {
+            let net = self.ledger[s as usize];
+            if credit && net > 0 {
+                self.created[s as usize] += net as u32;
+            }
+            self.ledger[s as usize] = 0;
+        }
+        self.ledger_touched.clear();
+    }
+
     fn update_cell(cell: Cell, api: SandApi) {
-        if cell.clock - api.universe.generation == 1 {
+        if cell.clock.wrapping_sub(api.universe.generation) == 1 {
             return;
         }
 
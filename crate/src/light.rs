@@ -0,0 +1,59 @@
+use Species;
+use Universe;
+
+use std::f32::consts::PI;
+use wasm_bindgen::prelude::*;
+
+// how far a light cell shines; the level falls off linearly to nothing here
+const LIGHT_REACH: i32 = 24;
+// enough rays that neighbouring ones are less than a cell apart at the edge
+const LIGHT_RAYS: i32 = 8 * LIGHT_REACH;
+
+#[wasm_bindgen]
+impl Universe {
+    // one byte per cell, laid out like cells(): 255 right at a light
+    // source, fading to 0 with distance and in the shadow of solids
+    pub fn light(&self) -> *const u8 {
+        self.light.as_ptr()
+    }
+}
+
+impl Universe {
+    // recomputed from scratch every tick. each source casts rays outward
+    // that stop at the first solid cell, which is lit itself so walls show
+    // their bright side.
+    pub(crate) fn light_pass(&mut self) {
+        for level in self.light.iter_mut() {
+            *level = 0;
+        }
+        for sx in 0..self.width {
+            for sy in 0..self.height {
+                if self.get_cell(sx, sy).species == Species::Light {
+                    self.shine(sx, sy);
+                }
+            }
+        }
+    }
+
+    fn shine(&mut self, sx: i32, sy: i32) {
+        for ray in 0..LIGHT_RAYS {
+            let angle = ray as f32 * 2.0 * PI / LIGHT_RAYS as f32;
+            let (dx, dy) = (angle.cos(), angle.sin());
+            for step in 0..LIGHT_REACH {
+                let x = (sx as f32 + 0.5 + dx * step as f32).floor() as i32;
+                let y = (sy as f32 + 0.5 + dy * step as f32).floor() as i32;
+                if x < 0 || x > self.width - 1 || y < 0 || y > self.height - 1 {
+                    break;
+                }
+                let i = self.get_index(x, y);
+                let level = (255 * (LIGHT_REACH - step) / LIGHT_REACH) as u8;
+                if self.light[i] < level {
+                    self.light[i] = level;
+                }
+                if self.cells[i].species.is_solid() {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use Species;
+use Universe;
+
+use wasm_bindgen::prelude::*;
+
+// built-in starting scenes, scaled to whatever size the universe is
+#[wasm_bindgen]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    EmptyWithFloor = 0,
+    WaterTank = 1,
+    LavaLamp = 2,
+    SandHourglass = 3,
+}
+
+#[wasm_bindgen]
+impl Universe {
+    // clears the universe and builds the scene
+    pub fn apply_preset(&mut self, preset: Preset) {
+        self.reset();
+        let (w, h) = (self.width, self.height);
+        match preset {
+            Preset::EmptyWithFloor => {
+                self.fill_rect(0, h - 2, w, h, Species::Wall);
+            }
+            Preset::WaterTank => {
+                let (x0, x1, y0) = (w / 8, w - w / 8, h / 3);
+                self.tub(x0, x1, y0, h);
+                self.fill_rect(x0 + 1, y0 + h / 8, x1 - 1, h - 2, Species::Water);
+            }
+            Preset::LavaLamp => {
+                // oil blobs at the bottom of a tall water column float up
+                // through it
+                let (x0, x1) = (w / 2 - w / 8, w / 2 + w / 8);
+                self.tub(x0, x1, h / 8, h);
+                self.fill_rect(x0 + 1, h - 2 - h / 10, x1 - 1, h - 2, Species::Oil);
+                self.fill_rect(x0 + 1, h / 4, x1 - 1, h - 2, Species::Water);
+            }
+            Preset::SandHourglass => {
+                self.fill_rect(0, h - 2, w, h, Species::Wall);
+                let (mid, neck) = (h / 2, 2);
+                for y in 0..h - 2 {
+                    // walls close in towards the middle, leaving a narrow neck
+                    let inset = (mid - (y - mid).abs()) * (w / 2 - neck) / mid;
+                    self.fill_rect(0, y, inset, y + 1, Species::Wall);
+                    self.fill_rect(w - inset, y, w, y + 1, Species::Wall);
+                }
+                self.fill_rect(0, h / 8, w, mid / 2 + h / 8, Species::Sand);
+            }
+        }
+    }
+}
+
+impl Universe {
+    // a walled container open at the top: floor 2 cells thick, sides 1 cell
+    fn tub(&mut self, x0: i32, x1: i32, y0: i32, y1: i32) {
+        self.fill_rect(x0, y1 - 2, x1, y1, Species::Wall);
+        self.fill_rect(x0, y0, x0 + 1, y1, Species::Wall);
+        self.fill_rect(x1 - 1, y0, x1, y1, Species::Wall);
+    }
+}
@@ -0,0 +1,193 @@
+use Species;
+use Universe;
+
+use wasm_bindgen::prelude::*;
+
+// replay layout: width and height as big-endian u16s, then one entry per
+// recorded call, each starting with its kind and the generation it ran at:
+//   tick:     TICK, generation
+//   paint:    PAINT, generation, x, y, size (big-endian i16s), species
+//   add_wind: WIND, generation, x, y (big-endian i16s), dx, dy
+//   apply_uniform_wind: UNIFORM_WIND, generation, dx, dy as pushed per cell
+//   paint_wind_brush: WIND_BRUSH, generation, x, y, radius (big-endian
+//     i16s), dx, dy
+//   apply_vortex: VORTEX, generation, cx, cy, radius (big-endian i16s),
+//     strength (big-endian f32)
+const TICK: u8 = 0;
+const PAINT: u8 = 1;
+const WIND: u8 = 2;
+const UNIFORM_WIND: u8 = 3;
+const WIND_BRUSH: u8 = 4;
+const VORTEX: u8 = 5;
+
+enum Input {
+    Tick,
+    Paint(i32, i32, i32, Species),
+    Wind(i32, i32, i8, i8),
+    UniformWind(i8, i8),
+    WindBrush(i32, i32, i32, i8, i8),
+    Vortex(i32, i32, f32, i32),
+}
+
+#[wasm_bindgen]
+impl Universe {
+    // log every paint, tick and add_wind from here on (anything built on
+    // them, like paint_pressure, is logged as those calls;
+    // apply_uniform_wind, paint_wind_brush and apply_vortex are logged once
+    // each rather than cell by cell).
+    // a replay starts from a fresh universe, so for an exact reproduction
+    // start recording on one.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recording = None;
+    }
+
+    // the log so far, empty if nothing is being recorded
+    pub fn export_replay(&self) -> Vec<u8> {
+        match &self.recording {
+            Some(log) => {
+                let mut bytes = Vec::with_capacity(4 + log.len());
+                bytes.extend_from_slice(&(self.width as u16).to_be_bytes());
+                bytes.extend_from_slice(&(self.height as u16).to_be_bytes());
+                bytes.extend_from_slice(log);
+                bytes
+            }
+            None => Vec::new(),
+        }
+    }
+
+    // resets to a fresh universe and plays an exported log back into it
+    pub fn replay(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        if bytes.len() < 4 {
+            return Err(JsValue::from_str("replay is truncated"));
+        }
+        let width = u16::from_be_bytes([bytes[0], bytes[1]]) as i32;
+        let height = u16::from_be_bytes([bytes[2], bytes[3]]) as i32;
+        if width != self.width || height != self.height {
+            return Err(JsValue::from_str(&format!(
+                "replay is {}x{} but the universe is {}x{}",
+                width, height, self.width, self.height
+            )));
+        }
+        // parse it all up front so a bad log leaves the universe untouched
+        let mut inputs = Vec::new();
+        let mut rest = &bytes[4..];
+        while !rest.is_empty() {
+            let len = match rest[0] {
+                TICK => 2,
+                PAINT => 9,
+                WIND => 8,
+                UNIFORM_WIND => 4,
+                WIND_BRUSH => 10,
+                VORTEX => 12,
+                kind => return Err(JsValue::from_str(&format!("unknown replay entry {}", kind))),
+            };
+            if rest.len() < len {
+                return Err(JsValue::from_str("replay is truncated"));
+            }
+            let e = &rest[..len];
+            let at = |i: usize| i16::from_be_bytes([e[i], e[i + 1]]) as i32;
+            let input = match e[0] {
+                TICK => Input::Tick,
+                PAINT => match Species::from_u8(e[8]) {
+                    Some(species) => Input::Paint(at(2), at(4), at(6), species),
+                    None => {
+                        return Err(JsValue::from_str(&format!(
+                            "replay paints unknown species {}",
+                            e[8]
+                        )))
+                    }
+                },
+                WIND => Input::Wind(at(2), at(4), e[6] as i8, e[7] as i8),
+                UNIFORM_WIND => Input::UniformWind(e[2] as i8, e[3] as i8),
+                WIND_BRUSH => Input::WindBrush(at(2), at(4), at(6), e[8] as i8, e[9] as i8),
+                _ => {
+                    let strength = f32::from_be_bytes([e[8], e[9], e[10], e[11]]);
+                    Input::Vortex(at(2), at(4), strength, at(6))
+                }
+            };
+            inputs.push((e[1], input));
+            rest = &rest[len..];
+        }
+
+        *self = Universe::new(width, height);
+        for (n, (generation, input)) in inputs.into_iter().enumerate() {
+            if generation != self.generation {
+                return Err(JsValue::from_str(&format!(
+                    "replay diverged at entry {}",
+                    n
+                )));
+            }
+            match input {
+                Input::Tick => self.tick(),
+                Input::Paint(x, y, size, species) => self.paint(x, y, size, species),
+                Input::Wind(x, y, dx, dy) => self.add_wind(x, y, dx, dy),
+                Input::UniformWind(dx, dy) => self.uniform_wind(dx, dy),
+                Input::WindBrush(x, y, radius, dx, dy) => self.wind_brush(x, y, radius, dx, dy),
+                Input::Vortex(cx, cy, strength, radius) => self.vortex(cx, cy, strength, radius),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Universe {
+    pub(crate) fn record_tick(&mut self) {
+        let generation = self.generation;
+        if let Some(log) = &mut self.recording {
+            log.extend_from_slice(&[TICK, generation]);
+        }
+    }
+
+    pub(crate) fn record_paint(&mut self, x: i32, y: i32, size: i32, species: Species) {
+        let generation = self.generation;
+        if let Some(log) = &mut self.recording {
+            log.extend_from_slice(&[PAINT, generation]);
+            for v in [x, y, size].iter() {
+                log.extend_from_slice(&(*v as i16).to_be_bytes());
+            }
+            log.push(species as u8);
+        }
+    }
+
+    pub(crate) fn record_wind(&mut self, x: i32, y: i32, dx: i8, dy: i8) {
+        let generation = self.generation;
+        if let Some(log) = &mut self.recording {
+            log.extend_from_slice(&[WIND, generation]);
+            log.extend_from_slice(&(x as i16).to_be_bytes());
+            log.extend_from_slice(&(y as i16).to_be_bytes());
+            log.extend_from_slice(&[dx as u8, dy as u8]);
+        }
+    }
+
+    pub(crate) fn record_uniform_wind(&mut self, dx: i8, dy: i8) {
+        let generation = self.generation;
+        if let Some(log) = &mut self.recording {
+            log.extend_from_slice(&[UNIFORM_WIND, generation, dx as u8, dy as u8]);
+        }
+    }
+
+    pub(crate) fn record_wind_brush(&mut self, x: i32, y: i32, radius: i32, dx: i8, dy: i8) {
+        let generation = self.generation;
+        if let Some(log) = &mut self.recording {
+            log.extend_from_slice(&[WIND_BRUSH, generation]);
+            for v in [x, y, radius].iter() {
+                log.extend_from_slice(&(*v as i16).to_be_bytes());
+            }
+            log.extend_from_slice(&[dx as u8, dy as u8]);
+        }
+    }
+    pub(crate) fn record_vortex(&mut self, cx: i32, cy: i32, strength: f32, radius: i32) {
+        let generation = self.generation;
+        if let Some(log) = &mut self.recording {
+            log.extend_from_slice(&[VORTEX, generation]);
+            for v in [cx, cy, radius].iter() {
+                log.extend_from_slice(&(*v as i16).to_be_bytes());
+            }
+            log.extend_from_slice(&strength.to_be_bytes());
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use super::heat::BOIL_POINT;
 use super::utils::*;
 use Cell;
 use SandApi;
@@ -9,6 +10,9 @@ use std::mem;
 use wasm_bindgen::prelude::*;
 // use web_sys::console;
 
+// These discriminants are written into saved scenes and read by the shaders,
+// so they must never be renumbered. New species take the next free number
+// and become LAST_SPECIES; 10 (Sink) and 21 (X) are retired and stay reserved.
 #[wasm_bindgen]
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -34,13 +38,233 @@ pub enum Species {
     Dust = 14,
     Oil = 16,
     Rocket = 17,
+    Bomb = 20,
+    Vine = 22,
+    Pump = 23,
+    Coral = 24,
+    Slime = 25,
+    Nanite = 26,
+    Firework = 27,
+    Clay = 28,
+    Laser = 29,
+    Mirror = 30,
+    Leaf = 31,
+    Bubble = 32,
+    Electric = 33,
+    Charge = 34,
+    Web = 35,
+    Rubber = 36,
+    Geyser = 37,
+    Sawdust = 38,
+    Grass = 39,
+    Honey = 40,
+    Moss = 41,
+    Crystal = 42,
+    Smoke = 43,
+    Antimatter = 44,
+    Tar = 45,
+    Radioactive = 46,
+    Balloon = 47,
+    Dynamite = 48,
+    Glue = 49,
+    Phosphorus = 50,
+    Soil = 51,
+    Confetti = 52,
+    Membrane = 53,
+    Frost = 54,
+    Light = 55,
+    Conveyor = 56,
+    Egg = 57,
+    Spore = 58,
+    Battery = 59,
+    Needle = 60,
+    Dirt = 61,
+    Quicklime = 62,
+    SlakedLime = 63,
+    Mycelium = 64,
 }
 
+pub const LAST_SPECIES: Species = Species::Mycelium;
+
+const BOMB_FUSE: u8 = 60;
+const BOMB_RADIUS: i32 = 6;
+const VINE_GROWTH: i32 = 8;
+const PUMP_REACH: i32 = 64;
+const CORAL_GROWTH: i32 = 12;
+const SLIME_REACH: u8 = 40;
+const NANITE_DECAY: i32 = 40;
+const FIREWORK_SPREAD: i32 = 6;
+const ACID_FUME_CHANCE: i32 = 4;
+const CLAY_DRY: i32 = 200;
+const CLAY_HEAT: i32 = 4;
+const LASER_RANGE: i32 = 120;
+const LEAF_SHED: i32 = 500;
+const LEAF_LIFE: u8 = 200;
+const DUST_CLOUD: i32 = 3;
+const DUST_CLOUD_MIN: i32 = 12;
+const CHARGE_CAPACITY: u8 = 4;
+const CHARGE_SPENT: u8 = 255;
+const WEB_SNAG: i32 = 4;
+const RUBBER_SPRING: i32 = 2;
+const GEYSER_BURST: i32 = 6;
+const GEYSER_KICK: u8 = 220;
+const PASTE_BURN: i32 = 12;
+const GRASS_GROWTH: i32 = 10;
+const LAVA_SOLID: i32 = 200;
+const MOSS_GROWTH: i32 = 16;
+const MOSS_DAMP: i32 = 3;
+// rb of a landed seed sitting out its sprout delay; sprouted seeds use
+// 1..=253 as they always have
+const SEED_WAITING: u8 = 254;
+const CRYSTAL_GROWTH: i32 = 12;
+const SMOKE_CHANCE: i32 = 3;
+const SMOKE_LIFE: u8 = 150;
+const TAR_STALL: i32 = 240;
+const TAR_BURN: u8 = 250;
+const TAR_ACID: i32 = 20;
+const DYNAMITE_FUSE: u8 = 12;
+const DYNAMITE_REACH: i32 = 24;
+// a lit stick's rb is DYNAMITE_LIT + direction * DYNAMITE_FUSE + fuse left
+pub const DYNAMITE_LIT: u8 = 128;
+const GLUE_DRY: u8 = 60;
+// rb of glue that has dried into a bond, see Universe::move_clump
+pub const GLUE_SET: u8 = 255;
+const PHOSPHORUS_AIR: i32 = 3;
+const SOIL_FEED: i32 = 6;
+const SOIL_NUTRIENTS: u8 = 8;
+const SOIL_REACH: i32 = 12;
+const CONFETTI_LIFE: u8 = 250;
+const MEMBRANE_SEEP: i32 = 8;
+const FROST_GROWTH: i32 = 6;
+const FROST_REACH: u8 = 12;
+const CONVEYOR_REACH: i32 = 64;
+const SPORE_RELEASE: i32 = 200;
+const SPORE_LIFE: u8 = 250;
+const BATTERY_CAPACITY: u8 = 50;
+const BATTERY_PULSE: u8 = 10;
+const BATTERY_RECHARGE: u8 = 10;
+// rb of an electric pulse sent out by a battery, which mustn't top the
+// battery back up
+const BATTERY_SPARK: u8 = 1;
+const BOIL_COOLING: i16 = 40;
+const DIRT_COMPACT: i32 = 8;
+const QUICKLIME_HEAT: i16 = 150;
+const MYCELIUM_GROWTH: i32 = 10;
+const MYCELIUM_FRUIT: i32 = 100;
+// most nutrients a mycelium cell holds on to
+const MYCELIUM_STORE: u8 = 4;
+const RADIOACTIVE_DECAY: i32 = 4;
+const RADIOACTIVE_MUTATE: i32 = 30;
+const RADIOACTIVE_HEAT: i16 = 3;
+// air under a balloon at least this hot gives it extra lift
+const BALLOON_LIFT: i16 = 50;
+// what a neighbour can be turned into
+const MUTATIONS: [Species; 8] = [
+    Species::Sand,
+    Species::Water,
+    Species::Stone,
+    Species::Dust,
+    Species::Plant,
+    Species::Oil,
+    Species::Gas,
+    Species::Slime,
+];
+// high bit of a water cell's rb marks dissolved mineral; the low bits are
+// still its own movement counter
+pub const SOLUTION: u8 = 128;
+
 impl Species {
+    // the species numbered `v`, or None for retired and unused numbers.
+    // bytes from outside the engine go through here, never a transmute.
+    pub fn from_u8(v: u8) -> Option<Species> {
+        match v {
+            10 | 21 => None,
+            v if v <= LAST_SPECIES as u8 => Some(unsafe { mem::transmute::<u8, Species>(v) }),
+            _ => None,
+        }
+    }
+
+    // central category table; new species should be added here as well as
+    // to update() so the engine's shared rules pick them up
+    pub fn is_flammable(&self) -> bool {
+        matches!(
+            self,
+            Species::Wood
+                | Species::Plant
+                | Species::Oil
+                | Species::Gas
+                | Species::Dust
+                | Species::Fungus
+                | Species::Seed
+                | Species::Vine
+                | Species::Slime
+                | Species::Leaf
+                | Species::Web
+                | Species::Sawdust
+                | Species::Grass
+                | Species::Honey
+                | Species::Moss
+                | Species::Tar
+        )
+    }
+    pub fn is_fluid(&self) -> bool {
+        matches!(
+            self,
+            Species::Water
+                | Species::Oil
+                | Species::Acid
+                | Species::Lava
+                | Species::Honey
+                | Species::Tar
+        )
+    }
+    pub fn is_powder(&self) -> bool {
+        matches!(
+            self,
+            Species::Sand
+                | Species::Dust
+                | Species::Seed
+                | Species::Bomb
+                | Species::Clay
+                | Species::Sawdust
+                | Species::Phosphorus
+                | Species::Soil
+                | Species::Dirt
+                | Species::Quicklime
+        )
+    }
+    pub fn is_solid(&self) -> bool {
+        matches!(
+            self,
+            Species::Wall
+                | Species::Stone
+                | Species::Wood
+                | Species::Ice
+                | Species::Cloner
+                | Species::Pump
+                | Species::Laser
+                | Species::Mirror
+                | Species::Charge
+                | Species::Rubber
+                | Species::Geyser
+                | Species::Crystal
+                | Species::Radioactive
+                | Species::Dynamite
+                | Species::Membrane
+                | Species::Conveyor
+                | Species::Battery
+                | Species::Needle
+                | Species::SlakedLime
+        )
+    }
+
     pub fn update(&self, cell: Cell, api: SandApi) {
         match self {
             Species::Empty => {}
             Species::Wall => {}
+            Species::Mirror => {}
+            Species::Light => {}
+            Species::SlakedLime => {}
             Species::Sand => update_sand(cell, api),
             Species::Dust => update_dust(cell, api),
             Species::Water => update_water(cell, api),
@@ -61,6 +285,47 @@ impl Species {
             Species::Oil => update_oil(cell, api),
             Species::Fungus => update_fungus(cell, api),
             Species::Seed => update_seed(cell, api),
+            Species::Bomb => update_bomb(cell, api),
+            Species::Vine => update_vine(cell, api),
+            Species::Pump => update_pump(cell, api),
+            Species::Coral => update_coral(cell, api),
+            Species::Slime => update_slime(cell, api),
+            Species::Nanite => update_nanite(cell, api),
+            Species::Firework => update_firework(cell, api),
+            Species::Clay => update_clay(cell, api),
+            Species::Laser => update_laser(cell, api),
+            Species::Leaf => update_leaf(cell, api),
+            Species::Bubble => update_bubble(cell, api),
+            Species::Electric => update_electric(cell, api),
+            Species::Charge => update_charge(cell, api),
+            Species::Web => update_web(cell, api),
+            Species::Rubber => update_rubber(cell, api),
+            Species::Geyser => update_geyser(cell, api),
+            Species::Sawdust => update_sawdust(cell, api),
+            Species::Grass => update_grass(cell, api),
+            Species::Honey => update_honey(cell, api),
+            Species::Moss => update_moss(cell, api),
+            Species::Crystal => update_crystal(cell, api),
+            Species::Smoke => update_smoke(cell, api),
+            Species::Antimatter => update_antimatter(cell, api),
+            Species::Tar => update_tar(cell, api),
+            Species::Radioactive => update_radioactive(cell, api),
+            Species::Balloon => update_balloon(cell, api),
+            Species::Dynamite => update_dynamite(cell, api),
+            Species::Glue => update_glue(cell, api),
+            Species::Phosphorus => update_phosphorus(cell, api),
+            Species::Soil => update_soil(cell, api),
+            Species::Confetti => update_confetti(cell, api),
+            Species::Membrane => update_membrane(cell, api),
+            Species::Frost => update_frost(cell, api),
+            Species::Conveyor => update_conveyor(cell, api),
+            Species::Egg => update_egg(cell, api),
+            Species::Spore => update_spore(cell, api),
+            Species::Battery => update_battery(cell, api),
+            Species::Needle => update_needle(cell, api),
+            Species::Dirt => update_dirt(cell, api),
+            Species::Quicklime => update_quicklime(cell, api),
+            Species::Mycelium => update_mycelium(cell, api),
             // Species::X => update_x(cell, api),
         }
     }
@@ -111,6 +376,12 @@ pub fn update_dust(cell: Cell, mut api: SandApi) {
         });
         return;
     }
+    if api.universe.dust_explosive && dust_cloud_ignites(&mut api) {
+        return;
+    }
+    if in_tar(&mut api) {
+        return;
+    }
 
     let nbr = api.get(0, 1);
     if nbr.species == Species::Empty {
@@ -127,6 +398,56 @@ pub fn update_dust(cell: Cell, mut api: SandApi) {
     }
 }
 
+// a thick enough cloud of airborne dust next to a flame goes up all at
+// once, rather than a grain at a time
+fn dust_cloud_ignites(api: &mut SandApi) -> bool {
+    if !NEIGHBORS
+        .iter()
+        .any(|&(dx, dy)| api.get(dx, dy).species == Species::Fire)
+    {
+        return false;
+    }
+    let mut airborne = 0;
+    for dx in -DUST_CLOUD..(DUST_CLOUD + 1) {
+        for dy in -DUST_CLOUD..(DUST_CLOUD + 1) {
+            // suspended: nothing but air or more dust underneath
+            let below = api.get_far(dx, dy + 1).species;
+            if api.get_far(dx, dy).species == Species::Dust
+                && (below == Species::Empty || below == Species::Dust)
+            {
+                airborne += 1;
+            }
+        }
+    }
+    if airborne < DUST_CLOUD_MIN {
+        return false;
+    }
+    for dx in -DUST_CLOUD..(DUST_CLOUD + 1) {
+        for dy in -DUST_CLOUD..(DUST_CLOUD + 1) {
+            let dust = api.get_far(dx, dy);
+            if dust.species == Species::Dust {
+                api.set_far(
+                    dx,
+                    dy,
+                    Cell {
+                        species: Species::Fire,
+                        ra: 150 + dust.ra / 10,
+                        rb: 0,
+                        clock: 0,
+                    },
+                );
+            }
+        }
+    }
+    api.set_fluid(Wind {
+        dx: 0,
+        dy: 0,
+        pressure: 120,
+        density: 5,
+    });
+    true
+}
+
 pub fn update_stone(cell: Cell, mut api: SandApi) {
     if api.get(-1, -1).species == Species::Stone && api.get(1, -1).species == Species::Stone {
         return;
@@ -165,6 +486,26 @@ pub fn update_stone(cell: Cell, mut api: SandApi) {
 }
 
 pub fn update_water(cell: Cell, mut api: SandApi) {
+    let i = api.universe.get_index(api.x, api.y);
+    if api.universe.temps[i] >= BOIL_POINT {
+        // boiling takes heat out of the spot it leaves
+        api.universe.temps[i] -= BOIL_COOLING;
+        api.set(0, 0, EMPTY_CELL);
+        return;
+    }
+    let evaporation = api.universe.evaporation;
+    if evaporation > 0 && api.rand_int(2048) < evaporation as i32 {
+        let mut exposed = 0;
+        for (dx, dy) in NEIGHBORS.iter().cloned() {
+            if api.get(dx, dy).species == Species::Empty {
+                exposed += 1;
+            }
+        }
+        if exposed >= 3 {
+            api.set(0, 0, EMPTY_CELL);
+            return;
+        }
+    }
     let mut dx = api.rand_dir();
     let below = api.get(0, 1);
     let dx1 = api.get(dx, 1);
@@ -198,7 +539,14 @@ pub fn update_water(cell: Cell, mut api: SandApi) {
     if dx0.species == Species::Empty && dxd.species == Species::Empty {
         // scoot double
         api.set(0, 0, dxd);
-        api.set(2 * dx, 0, Cell { rb: 6, ..cell });
+        api.set(
+            2 * dx,
+            0,
+            Cell {
+                rb: 6 | (cell.rb & SOLUTION),
+                ..cell
+            },
+        );
         let (dx, dy) = api.rand_vec_8();
         let nbr = api.get(dx, dy);
 
@@ -217,7 +565,14 @@ pub fn update_water(cell: Cell, mut api: SandApi) {
         }
     } else if dx0.species == Species::Empty || dx0.species == Species::Oil {
         api.set(0, 0, dx0);
-        api.set(dx, 0, Cell { rb: 3, ..cell });
+        api.set(
+            dx,
+            0,
+            Cell {
+                rb: 3 | (cell.rb & SOLUTION),
+                ..cell
+            },
+        );
         let (dx, dy) = api.rand_vec_8();
         let nbr = api.get(dx, dy);
         if nbr.species == Species::Water {
@@ -232,7 +587,7 @@ pub fn update_water(cell: Cell, mut api: SandApi) {
                 )
             }
         }
-    } else if cell.rb == 0 {
+    } else if cell.rb & !SOLUTION == 0 {
         if api.get(-dx, 0).species == Species::Empty {
             // bump
             api.set(
@@ -347,6 +702,23 @@ pub fn update_oil(cell: Cell, mut api: SandApi) {
         return;
     }
 
+    // afloat on open water it races out sideways into a thin slick
+    if api.get(0, 1).species == Species::Water {
+        let sx = if dx == 0 { api.rand_dir_2() } else { dx };
+        let mut reach = 0;
+        while reach < 2
+            && api.get(sx * (reach + 1), 0).species == Species::Empty
+            && api.get(sx * (reach + 1), 1).species == Species::Water
+        {
+            reach += 1;
+        }
+        if reach > 0 {
+            api.set(0, 0, EMPTY_CELL);
+            api.set(sx * reach, 0, new_cell);
+            return;
+        }
+    }
+
     if api.get(0, 1).species == Species::Empty {
         api.set(0, 0, EMPTY_CELL);
         api.set(0, 1, new_cell);
@@ -368,7 +740,11 @@ pub fn update_oil(cell: Cell, mut api: SandApi) {
 }
 
 pub fn update_gas(cell: Cell, mut api: SandApi) {
-    let (dx, dy) = api.rand_vec();
+    let (dx, mut dy) = api.rand_vec();
+    let rise = api.universe.gas_rise;
+    if rise > 0 && api.rand_int(256) < rise as i32 {
+        dy = -1;
+    }
 
     let nbr = api.get(dx, dy);
     // api.set_fluid(Wind {
@@ -591,7 +967,7 @@ pub fn update_rocket(cell: Cell, mut api: SandApi) {
 pub fn update_fire(cell: Cell, mut api: SandApi) {
     let ra = cell.ra;
     let mut degraded = cell.clone();
-    degraded.ra = ra - (2 + api.rand_dir()) as u8;
+    degraded.ra = ra.wrapping_sub((2 + api.rand_dir()) as u8);
 
     let (dx, dy) = api.rand_vec();
 
@@ -601,7 +977,8 @@ pub fn update_fire(cell: Cell, mut api: SandApi) {
         pressure: 1,
         density: 120,
     });
-    if api.get(dx, dy).species == Species::Gas || api.get(dx, dy).species == Species::Dust {
+    let fuel = api.get(dx, dy).species;
+    if fuel == Species::Gas || fuel == Species::Dust {
         api.set(
             dx,
             dy,
@@ -612,15 +989,34 @@ pub fn update_fire(cell: Cell, mut api: SandApi) {
                 clock: 0,
             },
         );
-        api.set_fluid(Wind {
-            dx: 0,
-            dy: 0,
-            pressure: 80,
-            density: 40,
-        });
+        if fuel == Species::Dust || api.universe.gas_explosive {
+            api.set_fluid(Wind {
+                dx: 0,
+                dy: 0,
+                pressure: 80,
+                density: 40,
+            });
+        }
     }
-    if ra < 5 || api.get(dx, dy).species == Species::Water {
+    if api.get(dx, dy).species == Species::Water {
         api.set(0, 0, EMPTY_CELL);
+    } else if ra < 5 {
+        // some of it goes up in smoke
+        if api.once_in(SMOKE_CHANCE) {
+            let ra = 100 + api.rand_int(40) as u8;
+            api.set(
+                0,
+                0,
+                Cell {
+                    species: Species::Smoke,
+                    ra,
+                    rb: 0,
+                    clock: 0,
+                },
+            );
+        } else {
+            api.set(0, 0, EMPTY_CELL);
+        }
     } else if api.get(dx, dy).species == Species::Empty {
         api.set(0, 0, EMPTY_CELL);
         api.set(dx, dy, degraded);
@@ -636,6 +1032,33 @@ pub fn update_lava(cell: Cell, mut api: SandApi) {
         pressure: 0,
         density: 60,
     });
+    // rb counts up while nothing hot is touching it, until it sets as stone
+    let mut cell = cell;
+    let cooling = api.universe.lava_cooling;
+    if cooling > 0 {
+        let warm = NEIGHBORS.iter().any(|&(dx, dy)| {
+            let nbr = api.get(dx, dy).species;
+            nbr == Species::Lava || nbr == Species::Fire
+        });
+        let cooled = if warm {
+            0
+        } else {
+            cell.rb as i32 + cooling as i32
+        };
+        if cooled >= LAVA_SOLID {
+            api.set(
+                0,
+                0,
+                Cell {
+                    species: Species::Stone,
+                    rb: 0,
+                    ..cell
+                },
+            );
+            return;
+        }
+        cell.rb = cooled as u8;
+    }
     let (dx, dy) = api.rand_vec();
 
     if api.get(dx, dy).species == Species::Gas || api.get(dx, dy).species == Species::Dust {
@@ -801,6 +1224,9 @@ pub fn update_ice(cell: Cell, mut api: SandApi) {
 
 pub fn update_plant(cell: Cell, mut api: SandApi) {
     let rb = cell.rb;
+    if rb == 0 {
+        shed_leaf(cell, &mut api);
+    }
 
     let mut i = api.rand_int(100);
     let (dx, dy) = api.rand_vec();
@@ -818,7 +1244,7 @@ pub fn update_plant(cell: Cell, mut api: SandApi) {
             },
         );
     }
-    if nbr_species == Species::Wood {
+    if nbr_species == Species::Wood && api.may_grow() {
         let (dx, dy) = api.rand_vec();
 
         let drift = (i % 15) - 7;
@@ -837,6 +1263,7 @@ pub fn update_plant(cell: Cell, mut api: SandApi) {
         }
     }
     if api.rand_int(100) > 80
+        && api.may_grow()
         && (nbr_species == Species::Water
             || nbr_species == Species::Fungus
                 && (api.get(-dx, dy).species == Species::Empty
@@ -948,19 +1375,49 @@ pub fn update_seed(cell: Cell, mut api: SandApi) {
         return;
     }
 
-    if rb == 0 {
+    // with a sprout delay set, a seed sitting somewhere it could germinate
+    // is marked SEED_WAITING and its ticks are counted in seed_waits
+    let delay = api.universe.seed_delay;
+    let waiting = rb == SEED_WAITING;
+    if rb == 0 || waiting {
         //falling
 
         let dxf = api.rand_dir(); //falling dx
         let nbr_species_below = api.get(dxf, 1).species;
-        if nbr_species_below == Species::Sand
+        let soil = nbr_species_below == Species::Sand
+            || nbr_species_below == Species::Soil
             || nbr_species_below == Species::Plant
-            || nbr_species_below == Species::Fungus
-        {
-            let rb = (api.rand_int(253) + 1) as u8;
+            || nbr_species_below == Species::Fungus;
+        let grounded = if api.universe.seed_needs_soil {
+            soil
+        } else {
+            api.get(0, 1).species != Species::Empty
+        };
+        let watered = !api.universe.seed_needs_water
+            || NEIGHBORS
+                .iter()
+                .any(|&(dx, dy)| api.get(dx, dy).species == Species::Water);
+        let i = api.universe.get_index(api.x, api.y);
+        if grounded && watered {
+            let waited = if waiting {
+                api.universe.seed_waits.get(&i).map_or(1, |w| w + 1)
+            } else {
+                1
+            };
+            let rb = if waited >= delay {
+                api.universe.seed_waits.remove(&i);
+                (api.rand_int(253) + 1) as u8
+            } else {
+                api.universe.seed_waits.insert(i, waited);
+                SEED_WAITING
+            };
             api.set(0, 0, Cell { rb, ..cell });
             return;
         }
+        if waiting {
+            api.universe.seed_waits.remove(&i);
+        }
+        let cell = Cell { rb: 0, ..cell };
 
         let nbr = api.get(0, 1);
         if nbr.species == Species::Empty {
@@ -1061,12 +1518,27 @@ pub fn update_fungus(cell: Cell, mut api: SandApi) {
             },
         );
     }
+    // healthy fungus open to the air now and then lets a spore go
+    if rb == 0 && api.get(0, -1).species == Species::Empty && api.once_in(SPORE_RELEASE) {
+        api.set(
+            0,
+            -1,
+            Cell {
+                species: Species::Spore,
+                ra: cell.ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+    }
     let mut i = api.rand_int(100);
 
     if nbr_species != Species::Empty
         && nbr_species != Species::Fungus
+        && nbr_species != Species::Spore
         && nbr_species != Species::Fire
         && nbr_species != Species::Ice
+        && api.may_grow()
     {
         let (dx, dy) = api.rand_vec();
 
@@ -1087,6 +1559,7 @@ pub fn update_fungus(cell: Cell, mut api: SandApi) {
     }
 
     if i > 9
+        && api.may_grow()
         && nbr_species == Species::Wood
         && api.get(-dx, dy).species == Species::Wood
         && api.get(dx, -dy).species == Species::Wood
@@ -1191,23 +1664,21 @@ pub fn update_acid(cell: Cell, mut api: SandApi) {
         api.set(0, 0, EMPTY_CELL);
         api.set(-dx, 0, cell);
     } else {
-        if api.get(0, 1).species != Species::Wall && api.get(0, 1).species != Species::Acid {
-            api.set(0, 0, EMPTY_CELL);
+        if acid_eats(&mut api, 0, 1) {
+            let residue = acid_residue(&mut api);
+            api.set(0, 0, residue);
             api.set(0, 1, degraded);
-        } else if api.get(dx, 0).species != Species::Wall && api.get(dx, 0).species != Species::Acid
-        {
-            api.set(0, 0, EMPTY_CELL);
+        } else if acid_eats(&mut api, dx, 0) {
+            let residue = acid_residue(&mut api);
+            api.set(0, 0, residue);
             api.set(dx, 0, degraded);
-        } else if api.get(-dx, 0).species != Species::Wall
-            && api.get(-dx, 0).species != Species::Acid
-        {
-            api.set(0, 0, EMPTY_CELL);
+        } else if acid_eats(&mut api, -dx, 0) {
+            let residue = acid_residue(&mut api);
+            api.set(0, 0, residue);
             api.set(-dx, 0, degraded);
-        } else if api.get(0, -1).species != Species::Wall
-            && api.get(0, -1).species != Species::Acid
-            && api.get(0, -1).species != Species::Empty
-        {
-            api.set(0, 0, EMPTY_CELL);
+        } else if acid_eats(&mut api, 0, -1) && api.get(0, -1).species != Species::Empty {
+            let residue = acid_residue(&mut api);
+            api.set(0, 0, residue);
             api.set(0, -1, degraded);
         } else {
             api.set(0, 0, cell);
@@ -1215,6 +1686,27 @@ pub fn update_acid(cell: Cell, mut api: SandApi) {
     }
 }
 
+// wall, acid itself, and anything marked with set_acid_resistant are safe
+fn acid_eats(api: &mut SandApi, dx: i32, dy: i32) -> bool {
+    let species = api.get(dx, dy).species;
+    species != Species::Acid && !api.universe.acid_resistant[species as usize]
+}
+
+// what acid leaves in its old spot after dissolving something
+fn acid_residue(api: &mut SandApi) -> Cell {
+    if api.universe.acid_fumes && api.once_in(ACID_FUME_CHANCE) {
+        let ra = 80 + api.rand_int(40) as u8;
+        Cell {
+            species: Species::Gas,
+            ra,
+            rb: 0,
+            clock: 0,
+        }
+    } else {
+        EMPTY_CELL
+    }
+}
+
 pub fn update_mite(cell: Cell, mut api: SandApi) {
     let mut i = api.rand_int(100);
     let mut dx = 0;
@@ -1292,3 +1784,1789 @@ pub fn update_mite(cell: Cell, mut api: SandApi) {
         }
     }
 }
+
+pub fn update_bomb(cell: Cell, mut api: SandApi) {
+    if cell.rb == 0 {
+        // light the fuse
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: BOMB_FUSE,
+                ..cell
+            },
+        );
+        return;
+    }
+    if cell.rb == 1 {
+        api.explode(BOMB_RADIUS);
+        return;
+    }
+    let bomb = Cell {
+        rb: cell.rb - 1,
+        ..cell
+    };
+
+    let dx = api.rand_dir_2();
+    let nbr = api.get(0, 1);
+    if nbr.species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, bomb);
+    } else if api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, bomb);
+    } else if nbr.species == Species::Water
+        || nbr.species == Species::Gas
+        || nbr.species == Species::Oil
+        || nbr.species == Species::Acid
+    {
+        api.set(0, 0, nbr);
+        api.set(0, 1, bomb);
+    } else {
+        api.set(0, 0, bomb);
+    }
+}
+
+pub fn update_vine(cell: Cell, mut api: SandApi) {
+    let (dx, dy) = api.rand_vec();
+    let nbr_species = api.get(dx, dy).species;
+    if nbr_species == Species::Fire || nbr_species == Species::Lava {
+        let ra = 60 + api.rand_int(40) as u8;
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Fire,
+                ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        return;
+    }
+    shed_leaf(cell, &mut api);
+
+    if !api.may_grow() || !api.once_in(VINE_GROWTH) {
+        return;
+    }
+    // mostly climb, sometimes creep sideways
+    let gx = api.rand_dir();
+    let gy = if api.once_in(3) { 0 } else { -1 };
+    if (gx == 0 && gy == 0) || api.get(gx, gy).species != Species::Empty {
+        return;
+    }
+    // only grow into cells that still have something to cling to
+    for sx in -1..2 {
+        for sy in -1..2 {
+            let s = api.get(gx + sx, gy + sy).species;
+            if s == Species::Wall || s == Species::Wood || s == Species::Stone {
+                let drift = (api.rand_int(100) % 15) - 7;
+                api.set(
+                    gx,
+                    gy,
+                    Cell {
+                        ra: (cell.ra as i32 + drift) as u8,
+                        rb: 0,
+                        ..cell
+                    },
+                );
+                return;
+            }
+        }
+    }
+}
+
+pub fn update_pump(cell: Cell, mut api: SandApi) {
+    // rb holds 100 + the push direction, like the rocket's heading in ra
+    if cell.rb < 100 {
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: 100 + join_dy_dx(0, -1),
+                ..cell
+            },
+        );
+        return;
+    }
+    let (dx, dy) = split_dy_dx(cell.rb - 100);
+    let (wired, charged) = battery_power(&mut api, 0, 0);
+    if wired && !charged {
+        return;
+    }
+    let source = api.get(-dx, -dy);
+    if source.species != Species::Water && source.species != Species::Oil {
+        return;
+    }
+    // push the pulled cell onto the far end of whatever fluid is already
+    // in the pipe, so a column can rise rather than stall against itself
+    let mut reach = 1;
+    loop {
+        let s = api.get_far(dx * reach, dy * reach).species;
+        if s == Species::Empty {
+            break;
+        }
+        if (s != Species::Water && s != Species::Oil) || reach >= PUMP_REACH {
+            return;
+        }
+        reach += 1;
+    }
+    api.set(-dx, -dy, EMPTY_CELL);
+    api.set_far(dx * reach, dy * reach, source);
+}
+
+pub fn update_coral(cell: Cell, mut api: SandApi) {
+    if !api.may_grow() || !api.once_in(CORAL_GROWTH) {
+        return;
+    }
+    // grows by displacing water, so it can never leave the pool it's in
+    let gx = api.rand_dir();
+    let gy = if api.once_in(4) { 0 } else { -1 };
+    if (gx == 0 && gy == 0) || api.get(gx, gy).species != Species::Water {
+        return;
+    }
+    // keep branches thin: don't grow alongside existing coral
+    for sx in [-1, 1].iter().cloned() {
+        if gx + sx != 0 && api.get(gx + sx, gy).species == Species::Coral {
+            return;
+        }
+    }
+    let drift = (api.rand_int(100) % 11) - 5;
+    api.set(
+        gx,
+        gy,
+        Cell {
+            ra: (cell.ra as i32 + drift) as u8,
+            rb: 0,
+            ..cell
+        },
+    );
+}
+
+// would moving this cell to (dx, 1) keep the slime around it in one piece?
+// checked by flood filling a small window that covers both spots.
+fn slime_holds_together(api: &mut SandApi, dx: i32) -> bool {
+    let mut slime = [[false; 4]; 5];
+    for x in -2..3 {
+        for y in -1..3 {
+            if (x != 0 || y != 0) && api.get(x, y).species == Species::Slime {
+                slime[(x + 2) as usize][(y + 1) as usize] = true;
+            }
+        }
+    }
+    slime[(dx + 2) as usize][2] = true;
+
+    let mut seen = [[false; 4]; 5];
+    seen[(dx + 2) as usize][2] = true;
+    let mut stack = vec![(dx, 1)];
+    let mut reached = 0;
+    while let Some((x, y)) = stack.pop() {
+        reached += 1;
+        for nx in (x - 1)..(x + 2) {
+            for ny in (y - 1)..(y + 2) {
+                if !(-2..=2).contains(&nx) || !(-1..=2).contains(&ny) {
+                    continue;
+                }
+                let (ix, iy) = ((nx + 2) as usize, (ny + 1) as usize);
+                if slime[ix][iy] && !seen[ix][iy] {
+                    seen[ix][iy] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+    }
+    if reached < 2 {
+        return false;
+    }
+    for x in -1..2 {
+        for y in -1..2 {
+            let (ix, iy) = ((x + 2) as usize, (y + 1) as usize);
+            if slime[ix][iy] && !seen[ix][iy] {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+pub fn update_slime(cell: Cell, mut api: SandApi) {
+    let (dx, dy) = api.rand_vec();
+    let nbr_species = api.get(dx, dy).species;
+    if nbr_species == Species::Fire || nbr_species == Species::Lava {
+        let ra = 40 + api.rand_int(40) as u8;
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Fire,
+                ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        return;
+    }
+
+    // rb is how far (through slime) this cell is from something to rest on,
+    // or 0 if nothing is holding the blob up
+    let below = api.get(0, 1).species;
+    let mut support = 0;
+    if below != Species::Empty && below != Species::Slime {
+        support = 1;
+    } else {
+        for (sx, sy) in NEIGHBORS.iter().cloned() {
+            let nbr = api.get(sx, sy);
+            if nbr.species == Species::Slime && nbr.rb > 0 && (support == 0 || nbr.rb < support) {
+                support = nbr.rb + 1;
+            }
+        }
+        if support > SLIME_REACH {
+            support = 0;
+        }
+    }
+    let slime = Cell {
+        rb: support,
+        ..cell
+    };
+
+    if support == 0 {
+        if below == Species::Empty {
+            // fall as a unit, taking the slime stacked above along
+            let mut top = 0;
+            while top < SLIME_REACH as i32 && api.get_far(0, -(top + 1)).species == Species::Slime {
+                top += 1;
+            }
+            api.set(0, 1, slime);
+            for k in 0..top {
+                let above = api.get_far(0, -(k + 1));
+                api.set_far(0, -k, above);
+            }
+            api.set_far(0, -top, EMPTY_CELL);
+        } else {
+            api.set(0, 0, slime);
+        }
+        return;
+    }
+    api.set(0, 0, slime);
+
+    // supported slime sags slowly, but never so far that it tears
+    if !api.once_in(8) {
+        return;
+    }
+    let dx = api.rand_dir();
+    if api.get(dx, 1).species == Species::Empty && slime_holds_together(&mut api, dx) {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, slime);
+    }
+}
+
+pub fn update_nanite(cell: Cell, mut api: SandApi) {
+    let full = api.universe.nanites >= api.universe.nanite_cap;
+    if full && api.once_in(NANITE_DECAY) {
+        api.universe.nanites = api.universe.nanites.saturating_sub(1);
+        api.set(0, 0, EMPTY_CELL);
+        return;
+    }
+    // rewrite ourselves to keep the clock fresh; a skipped update would
+    // drop out of the tally and let the swarm overshoot its cap
+    api.set(0, 0, cell);
+    api.universe.nanite_tally += 1;
+    if full || !api.once_in(3) {
+        return;
+    }
+
+    let (dx, dy) = api.rand_vec_8();
+    let nbr_species = api.get(dx, dy).species;
+    if nbr_species == Species::Empty
+        || nbr_species == Species::Wall
+        || nbr_species == Species::Nanite
+    {
+        return;
+    }
+    api.set(dx, dy, Cell { rb: 0, ..cell });
+    api.universe.nanites += 1;
+    api.universe.nanite_tally += 1;
+}
+
+pub fn update_firework(cell: Cell, mut api: SandApi) {
+    // rb counts the cells climbed so far
+    let fuse = api.universe.firework_fuse;
+    if cell.rb < fuse && api.get(0, -1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(
+            0,
+            -1,
+            Cell {
+                rb: cell.rb + 1,
+                ..cell
+            },
+        );
+        return;
+    }
+
+    // burst: scatter sparks over randomly chosen empty cells nearby
+    api.set(0, 0, EMPTY_CELL);
+    let mut spots = Vec::new();
+    for dx in -FIREWORK_SPREAD..(FIREWORK_SPREAD + 1) {
+        for dy in -FIREWORK_SPREAD..(FIREWORK_SPREAD + 1) {
+            if dx * dx + dy * dy <= FIREWORK_SPREAD * FIREWORK_SPREAD
+                && api.get_far(dx, dy).species == Species::Empty
+            {
+                spots.push((dx, dy));
+            }
+        }
+    }
+    let sparks = (api.universe.firework_burst as usize).min(spots.len());
+    for i in 0..sparks {
+        let j = i + api.rand_int((spots.len() - i) as i32) as usize;
+        spots.swap(i, j);
+        let (dx, dy) = spots[i];
+        let ra = 120 + api.rand_int(100) as u8;
+        api.set_far(
+            dx,
+            dy,
+            Cell {
+                species: Species::Fire,
+                ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+    }
+}
+
+pub fn update_clay(cell: Cell, mut api: SandApi) {
+    // rb counts ticks spent away from water; heat dries it faster, and
+    // moisture wicks through touching clay so a wet lump stays soft inside
+    let mut dryness = cell.rb as i32 + 1;
+    for (dx, dy) in NEIGHBORS.iter().cloned() {
+        let nbr = api.get(dx, dy);
+        match nbr.species {
+            Species::Water => dryness = -CLAY_DRY,
+            Species::Fire | Species::Lava => dryness += CLAY_HEAT,
+            Species::Clay => dryness = dryness.min(nbr.rb as i32 + 1),
+            _ => {}
+        }
+    }
+    dryness = dryness.max(0);
+    if dryness >= CLAY_DRY {
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Stone,
+                rb: 0,
+                ..cell
+            },
+        );
+        return;
+    }
+    let cell = Cell {
+        rb: dryness as u8,
+        ..cell
+    };
+
+    // packs tighter than sand: it only slides off a ledge now and then
+    let dx = api.rand_dir_2();
+    let nbr = api.get(0, 1);
+    if nbr.species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, cell);
+    } else if api.once_in(4)
+        && api.get(dx, 0).species == Species::Empty
+        && api.get(dx, 1).species == Species::Empty
+    {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, cell);
+    } else if nbr.species == Species::Water
+        || nbr.species == Species::Gas
+        || nbr.species == Species::Oil
+        || nbr.species == Species::Acid
+    {
+        api.set(0, 0, nbr);
+        api.set(0, 1, cell);
+    } else {
+        api.set(0, 0, cell);
+    }
+}
+
+pub fn update_laser(cell: Cell, mut api: SandApi) {
+    // rb holds 100 + the beam direction, like the pump
+    if cell.rb < 100 {
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: 100 + join_dy_dx(1, 0),
+                ..cell
+            },
+        );
+        return;
+    }
+    // the beam isn't drawn as cells; it's traced fresh every tick through
+    // empty space, bouncing off mirrors, until it hits something
+    let (mut dx, mut dy) = split_dy_dx(cell.rb - 100);
+    if dx == 0 && dy == 0 {
+        return;
+    }
+    let (mut x, mut y) = (0, 0);
+    for _ in 0..LASER_RANGE {
+        x += dx;
+        y += dy;
+        let target = api.get_far(x, y);
+        match target.species {
+            Species::Empty => {}
+            // rb 0 is "/", anything else is "\"
+            Species::Mirror if target.rb == 0 => {
+                let turned = (-dy, -dx);
+                dx = turned.0;
+                dy = turned.1;
+            }
+            Species::Mirror => {
+                let turned = (dy, dx);
+                dx = turned.0;
+                dy = turned.1;
+            }
+            species => {
+                if species.is_flammable() {
+                    let ra = 150 + api.rand_int(50) as u8;
+                    api.set_far(
+                        x,
+                        y,
+                        Cell {
+                            species: Species::Fire,
+                            ra,
+                            rb: 0,
+                            clock: 0,
+                        },
+                    );
+                }
+                return;
+            }
+        }
+    }
+}
+
+// now and then drop a leaf into the open air below
+fn shed_leaf(cell: Cell, api: &mut SandApi) {
+    if !api.once_in(LEAF_SHED) {
+        return;
+    }
+    let dx = api.rand_dir();
+    if api.get(dx, 1).species != Species::Empty {
+        return;
+    }
+    let drift = (api.rand_int(100) % 21) - 10;
+    api.set(
+        dx,
+        1,
+        Cell {
+            species: Species::Leaf,
+            ra: (cell.ra as i32 + drift) as u8,
+            rb: 0,
+            clock: 0,
+        },
+    );
+}
+
+pub fn update_leaf(cell: Cell, mut api: SandApi) {
+    // rb is age; old leaves rot away
+    if cell.rb >= LEAF_LIFE {
+        api.set(0, 0, EMPTY_CELL);
+        return;
+    }
+    let cell = Cell {
+        rb: cell.rb + 1,
+        ..cell
+    };
+    // drift down slowly, swaying side to side
+    let dx = api.rand_dir();
+    if api.once_in(2) && api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, cell);
+    } else if dx != 0 && api.once_in(3) && api.get(dx, 0).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 0, cell);
+    } else {
+        api.set(0, 0, cell);
+    }
+}
+
+pub fn update_bubble(cell: Cell, mut api: SandApi) {
+    // swap upward through liquid, wobbling now and then; pop once there's
+    // nothing left to rise through
+    let wobble = if api.once_in(4) { api.rand_dir() } else { 0 };
+    for dx in [wobble, 0].iter().cloned() {
+        let above = api.get(dx, -1);
+        if above.species == Species::Water || above.species == Species::Oil {
+            api.set(0, 0, above);
+            api.set(dx, -1, cell);
+            return;
+        }
+    }
+    api.set(0, 0, EMPTY_CELL);
+}
+
+pub fn update_electric(cell: Cell, mut api: SandApi) {
+    // a pulse lasts one update: it tops up any charge cells it touches
+    // and, unless a battery sent it, puts a little back into any battery
+    // that has run down
+    feed_charges(&mut api);
+    for (dx, dy) in NEIGHBORS.iter().cloned() {
+        let nbr = api.get(dx, dy);
+        if nbr.species == Species::Battery && nbr.rb > 0 && cell.rb != BATTERY_SPARK {
+            let rb = nbr
+                .rb
+                .saturating_add(BATTERY_RECHARGE)
+                .min(1 + BATTERY_CAPACITY);
+            api.set(dx, dy, Cell { rb, ..nbr });
+        }
+    }
+    api.set(0, 0, EMPTY_CELL);
+}
+
+fn feed_charges(api: &mut SandApi) {
+    for (dx, dy) in NEIGHBORS.iter().cloned() {
+        let nbr = api.get(dx, dy);
+        if nbr.species != Species::Charge {
+            continue;
+        }
+        // a charge that just fired soaks up its own pulse coming back
+        let rb = if nbr.rb == CHARGE_SPENT {
+            0
+        } else {
+            nbr.rb.saturating_add(1).min(CHARGE_CAPACITY)
+        };
+        api.set(dx, dy, Cell { rb, ..nbr });
+    }
+}
+
+pub fn update_battery(cell: Cell, mut api: SandApi) {
+    // rb is 1 + the charge left, so a fresh battery (rb 0) starts full
+    if cell.rb == 0 {
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: 1 + BATTERY_CAPACITY,
+                ..cell
+            },
+        );
+        return;
+    }
+    if cell.rb == 1 {
+        return;
+    }
+    let rb = cell.rb - 1;
+    api.set(0, 0, Cell { rb, ..cell });
+    if !rb.is_multiple_of(BATTERY_PULSE) {
+        return;
+    }
+    // every so often send a pulse into an empty cell beside a conductor
+    for (dx, dy) in NEIGHBORS.iter().cloned() {
+        if api.get(dx, dy).species != Species::Empty {
+            continue;
+        }
+        let wired = NEIGHBORS
+            .iter()
+            .any(|&(nx, ny)| api.get_far(dx + nx, dy + ny).species == Species::Charge);
+        if wired {
+            api.set(
+                dx,
+                dy,
+                Cell {
+                    species: Species::Electric,
+                    ra: 200,
+                    rb: BATTERY_SPARK,
+                    clock: 0,
+                },
+            );
+            return;
+        }
+    }
+}
+
+// (touching a battery, touching one with charge left) for the cell at
+// (dx, dy). machines wired to a battery only run while it lasts; ones
+// with no battery beside them run as they always have.
+fn battery_power(api: &mut SandApi, dx: i32, dy: i32) -> (bool, bool) {
+    let (mut wired, mut charged) = (false, false);
+    for (nx, ny) in NEIGHBORS.iter().cloned() {
+        let nbr = api.get_far(dx + nx, dy + ny);
+        if nbr.species == Species::Battery {
+            wired = true;
+            charged |= nbr.rb != 1;
+        }
+    }
+    (wired, charged)
+}
+
+pub fn update_charge(cell: Cell, mut api: SandApi) {
+    // rb counts stored pulses; once full, send one out, preferring below
+    if cell.rb != CHARGE_CAPACITY {
+        return;
+    }
+    for (dx, dy) in [(0, 1)].iter().chain(NEIGHBORS.iter()).cloned() {
+        if api.get(dx, dy).species == Species::Empty {
+            api.set(
+                dx,
+                dy,
+                Cell {
+                    species: Species::Electric,
+                    ra: 200,
+                    rb: 0,
+                    clock: 0,
+                },
+            );
+            api.set(
+                0,
+                0,
+                Cell {
+                    rb: CHARGE_SPENT,
+                    ..cell
+                },
+            );
+            return;
+        }
+    }
+}
+
+pub fn update_web(_cell: Cell, mut api: SandApi) {
+    let (dx, dy) = api.rand_vec();
+    if api.get(dx, dy).species == Species::Fire {
+        let ra = 120 + api.rand_int(40) as u8;
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Fire,
+                ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        return;
+    }
+    // the top of a web lets grains and drops through to the far side, but
+    // only now and then, and less often the thicker the web
+    let caught = api.get(0, -1);
+    if !caught.species.is_powder() && !caught.species.is_fluid() {
+        return;
+    }
+    // stop at the grid edge, which reads as web when that's the boundary
+    let mut depth = 1;
+    while api.y + depth < api.universe.height && api.get_far(0, depth).species == Species::Web {
+        depth += 1;
+    }
+    if api.get_far(0, depth).species == Species::Empty && api.once_in(WEB_SNAG * depth) {
+        api.set(0, -1, EMPTY_CELL);
+        api.set_far(0, depth, caught);
+    }
+}
+
+pub fn update_rubber(_cell: Cell, mut api: SandApi) {
+    // wind blowing straight into the rubber meets a pressure spike at its
+    // face, so the fluid sim turns it back and whatever rides it bounces
+    for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().cloned() {
+        let (nx, ny) = (api.x + dx, api.y + dy);
+        if nx < 0 || nx > api.universe.width - 1 || ny < 0 || ny > api.universe.height - 1 {
+            continue;
+        }
+        if api.get(dx, dy).species == Species::Rubber {
+            continue;
+        }
+        let wind = api.universe.winds[api.universe.get_index(nx, ny)];
+        // the fluid sim stores the axes swapped, see blow_wind
+        let along = if dx != 0 { wind.dy } else { wind.dx };
+        let incoming = (along as i32 - 126) * -(dx + dy);
+        if incoming <= 0 {
+            continue;
+        }
+        api.set_fluid_far(
+            dx,
+            dy,
+            Wind {
+                dx: 0,
+                dy: 0,
+                pressure: (incoming * RUBBER_SPRING).min(255) as u8,
+                density: 0,
+            },
+        );
+    }
+}
+
+pub fn update_geyser(cell: Cell, mut api: SandApi) {
+    // rb counts ticks since the last eruption
+    let timer = cell.rb.saturating_add(1);
+    if timer < api.universe.geyser_period {
+        api.set(0, 0, Cell { rb: timer, ..cell });
+        return;
+    }
+    api.set(0, 0, Cell { rb: 0, ..cell });
+    for up in 1..(GEYSER_BURST + 1) {
+        if api.get_far(0, -up).species != Species::Empty {
+            break;
+        }
+        let ra = 100 + api.rand_int(50) as u8;
+        api.set_far(
+            0,
+            -up,
+            Cell {
+                species: Species::Water,
+                ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        // kick the column upward through burns, the way fire lifts its smoke
+        api.set_fluid_far(
+            0,
+            -up,
+            Wind {
+                dx: 0,
+                dy: GEYSER_KICK,
+                pressure: 0,
+                density: 0,
+            },
+        );
+    }
+}
+
+pub fn update_sawdust(cell: Cell, mut api: SandApi) {
+    // rb is set once it has soaked up water and turned to paste
+    let paste = cell.rb != 0;
+    let (dx, dy) = api.rand_vec();
+    let nbr = api.get(dx, dy);
+    if (nbr.species == Species::Fire || nbr.species == Species::Lava)
+        && (!paste || api.once_in(PASTE_BURN))
+    {
+        let ra = 150 + api.rand_int(50) as u8;
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Fire,
+                ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        return;
+    }
+    if !paste && nbr.species == Species::Water {
+        api.set(dx, dy, EMPTY_CELL);
+        api.set(0, 0, Cell { rb: 1, ..cell });
+        return;
+    }
+    if in_tar(&mut api) {
+        return;
+    }
+
+    let dx = api.rand_dir();
+    let below = api.get(0, 1);
+    if below.species == Species::Empty && (paste || api.once_in(2)) {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, cell);
+    } else if api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, cell);
+    } else if paste && (below.species == Species::Water || below.species == Species::Oil) {
+        api.set(0, 0, below);
+        api.set(0, 1, cell);
+    } else {
+        api.set(0, 0, cell);
+    }
+}
+
+pub fn update_grass(cell: Cell, mut api: SandApi) {
+    let (dx, dy) = api.rand_vec();
+    let nbr_species = api.get(dx, dy).species;
+    if nbr_species == Species::Fire || nbr_species == Species::Lava {
+        let ra = 60 + api.rand_int(40) as u8;
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Fire,
+                ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        return;
+    }
+    if api.get(0, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, cell);
+        return;
+    }
+
+    if !api.may_grow() || !api.once_in(GRASS_GROWTH) {
+        return;
+    }
+    // creep sideways along the surface, stepping up or down with it: only
+    // onto open cells that sit directly on sand or soil
+    let gx = api.rand_dir_2();
+    for gy in [0, -1, 1].iter().cloned() {
+        let ground = api.get(gx, gy + 1).species;
+        if api.get(gx, gy).species == Species::Empty
+            && (ground == Species::Sand || ground == Species::Soil)
+        {
+            let drift = (api.rand_int(100) % 11) - 5;
+            api.set(
+                gx,
+                gy,
+                Cell {
+                    ra: (cell.ra as i32 + drift) as u8,
+                    rb: 0,
+                    ..cell
+                },
+            );
+            return;
+        }
+    }
+}
+
+pub fn update_honey(cell: Cell, mut api: SandApi) {
+    let (dx, dy) = api.rand_vec();
+    // takes real heat to set it alight
+    if api.get(dx, dy).species == Species::Lava {
+        let ra = 150 + api.rand_int(50) as u8;
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Fire,
+                ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        return;
+    }
+    // most ticks it just sits there; when it does move it mostly sinks
+    if api.rand_int(256) < api.universe.honey_stall as i32 {
+        return;
+    }
+    let dx = api.rand_dir_2();
+    if api.get(0, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, cell);
+    } else if api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, cell);
+    } else if api.once_in(4) && api.get(dx, 0).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 0, cell);
+    } else if api.get(0, 1).species == Species::Water {
+        let below = api.get(0, 1);
+        api.set(0, 0, below);
+        api.set(0, 1, cell);
+    }
+}
+
+// is there water within a few cells?
+fn damp(api: &mut SandApi, reach: i32) -> bool {
+    for dx in -reach..(reach + 1) {
+        for dy in -reach..(reach + 1) {
+            if api.get_far(dx, dy).species == Species::Water {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+pub fn update_moss(cell: Cell, mut api: SandApi) {
+    let (dx, dy) = api.rand_vec();
+    let nbr_species = api.get(dx, dy).species;
+    let on_fire = nbr_species == Species::Fire || nbr_species == Species::Lava;
+    if !on_fire && !api.once_in(MOSS_GROWTH) {
+        return;
+    }
+    // only burns, and only grows, depending on how wet it is around here
+    let wet = damp(&mut api, MOSS_DAMP);
+    if on_fire {
+        if !wet {
+            let ra = 60 + api.rand_int(40) as u8;
+            api.set(
+                0,
+                0,
+                Cell {
+                    species: Species::Fire,
+                    ra,
+                    rb: 0,
+                    clock: 0,
+                },
+            );
+        }
+        return;
+    }
+    if !wet || nbr_species != Species::Empty || !api.may_grow() {
+        return;
+    }
+    // keep to the face of stone or wall
+    for (sx, sy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().cloned() {
+        let surface = api.get(dx + sx, dy + sy).species;
+        if surface == Species::Stone || surface == Species::Wall {
+            let drift = (api.rand_int(100) % 11) - 5;
+            api.set(
+                dx,
+                dy,
+                Cell {
+                    ra: (cell.ra as i32 + drift) as u8,
+                    rb: 0,
+                    ..cell
+                },
+            );
+            return;
+        }
+    }
+}
+
+pub fn update_crystal(cell: Cell, mut api: SandApi) {
+    if !api.may_grow() || !api.once_in(CRYSTAL_GROWTH) {
+        return;
+    }
+    let (dx, dy) = api.rand_vec_8();
+    let nbr = api.get(dx, dy);
+    if nbr.species != Species::Water || nbr.rb & SOLUTION == 0 {
+        return;
+    }
+    // only grow into open solution, so it branches out in spikes instead of
+    // filling in as a blob
+    let mut touching = 0;
+    for (sx, sy) in NEIGHBORS.iter().cloned() {
+        if api.get(dx + sx, dy + sy).species == Species::Crystal {
+            touching += 1;
+        }
+    }
+    if touching > 2 {
+        return;
+    }
+    api.set(dx, dy, Cell { rb: 0, ..cell });
+}
+
+pub fn update_smoke(cell: Cell, mut api: SandApi) {
+    // rb is its age; it thins out faster once it can't rise any further
+    let blocked = api.get(0, -1).species != Species::Empty
+        && api.get(-1, -1).species != Species::Empty
+        && api.get(1, -1).species != Species::Empty;
+    let age = cell.rb.saturating_add(if blocked { 4 } else { 1 });
+    if age >= SMOKE_LIFE || (age > SMOKE_LIFE / 2 && api.once_in(SMOKE_LIFE as i32)) {
+        api.set(0, 0, EMPTY_CELL);
+        return;
+    }
+    let aged = Cell { rb: age, ..cell };
+    // drift up and to either side, spreading out as it goes
+    let dx = api.rand_int(3) - 1;
+    let dy = if api.once_in(3) { 0 } else { -1 };
+    if api.get(dx, dy).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, dy, aged);
+    } else {
+        api.set(0, 0, aged);
+    }
+}
+
+pub fn update_antimatter(cell: Cell, mut api: SandApi) {
+    // takes the first thing touching it down with it; only wall holds it
+    for (dx, dy) in NEIGHBORS.iter().cloned() {
+        let nbr = api.get(dx, dy).species;
+        if nbr != Species::Empty && nbr != Species::Wall && nbr != Species::Antimatter {
+            api.set(dx, dy, EMPTY_CELL);
+            api.set(0, 0, EMPTY_CELL);
+            api.set_fluid(Wind {
+                dx: 0,
+                dy: 0,
+                pressure: 60,
+                density: 0,
+            });
+            return;
+        }
+    }
+    let dx = api.rand_dir_2();
+    if api.get(0, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, cell);
+    } else if api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, cell);
+    }
+}
+
+// light grains that touch tar are stuck fast
+fn in_tar(api: &mut SandApi) -> bool {
+    NEIGHBORS
+        .iter()
+        .any(|&(dx, dy)| api.get(dx, dy).species == Species::Tar)
+}
+
+pub fn update_tar(cell: Cell, mut api: SandApi) {
+    let (dx, dy) = api.rand_vec();
+    let nbr = api.get(dx, dy).species;
+    // rb counts down while it's alight, shedding long-lived flames
+    let mut cell = cell;
+    if cell.rb == 0 && (nbr == Species::Fire || nbr == Species::Lava) {
+        cell.rb = TAR_BURN;
+    }
+    if cell.rb > 0 {
+        if cell.rb == 1 {
+            api.set(
+                0,
+                0,
+                Cell {
+                    species: Species::Fire,
+                    ra: 250,
+                    rb: 0,
+                    clock: 0,
+                },
+            );
+            return;
+        }
+        cell.rb -= 1;
+        if nbr == Species::Empty && api.once_in(4) {
+            let ra = 200 + api.rand_int(50) as u8;
+            api.set(
+                dx,
+                dy,
+                Cell {
+                    species: Species::Fire,
+                    ra,
+                    rb: 0,
+                    clock: 0,
+                },
+            );
+        }
+        api.set(0, 0, cell);
+    }
+    // acid only wears it away slowly, see Universe::new
+    if nbr == Species::Acid && api.once_in(TAR_ACID) {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, dy, EMPTY_CELL);
+        return;
+    }
+    if api.rand_int(256) < TAR_STALL {
+        return;
+    }
+    let dx = api.rand_dir_2();
+    if api.get(0, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, cell);
+    } else if api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, cell);
+    } else if api.once_in(4) && api.get(dx, 0).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 0, cell);
+    } else if api.get(0, 1).species == Species::Water {
+        let below = api.get(0, 1);
+        api.set(0, 0, below);
+        api.set(0, 1, cell);
+    }
+}
+
+pub fn update_radioactive(cell: Cell, mut api: SandApi) {
+    // rb is how far it has decayed, ticking up now and then until it's spent
+    if api.once_in(RADIOACTIVE_DECAY) {
+        if cell.rb == 255 {
+            api.set(
+                0,
+                0,
+                Cell {
+                    species: Species::Stone,
+                    rb: 0,
+                    ..cell
+                },
+            );
+            return;
+        }
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: cell.rb + 1,
+                ..cell
+            },
+        );
+    }
+    // it runs hot, warming the temperature field around it
+    let (x, y) = (api.x, api.y);
+    api.universe.apply_heat_source(x, y, 1, RADIOACTIVE_HEAT);
+    let (dx, dy) = api.rand_vec_8();
+    let nbr = api.get(dx, dy);
+    // and melts ice and lights anything that burns
+    if nbr.species == Species::Ice {
+        api.set(
+            dx,
+            dy,
+            Cell {
+                species: Species::Water,
+                ..nbr
+            },
+        );
+    } else if nbr.species.is_flammable() && api.once_in(8) {
+        let ra = 120 + api.rand_int(60) as u8;
+        api.set(
+            dx,
+            dy,
+            Cell {
+                species: Species::Fire,
+                ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+    } else if nbr.species != Species::Empty
+        && nbr.species != Species::Wall
+        && nbr.species != Species::Radioactive
+        && api.once_in(RADIOACTIVE_MUTATE)
+    {
+        let species = MUTATIONS[api.rand_int(MUTATIONS.len() as i32) as usize];
+        api.set(
+            dx,
+            dy,
+            Cell {
+                species,
+                rb: 0,
+                ..nbr
+            },
+        );
+    }
+}
+
+pub fn update_balloon(cell: Cell, mut api: SandApi) {
+    if NEIGHBORS
+        .iter()
+        .any(|&(dx, dy)| api.get(dx, dy).species == Species::Fire)
+    {
+        // pop
+        api.set(0, 0, EMPTY_CELL);
+        api.set_fluid(Wind {
+            dx: 0,
+            dy: 0,
+            pressure: 40,
+            density: 0,
+        });
+        return;
+    }
+    // floats up, drifting a little, and pulls up whatever hangs under it.
+    // hot air underneath gives it extra lift.
+    let hot = api.y < api.universe.height - 1 && {
+        let i = api.universe.get_index(api.x, api.y + 1);
+        api.universe.temps[i] >= BALLOON_LIFT
+    };
+    let payload = api.get(0, 1);
+    let carrying = payload.species != Species::Empty
+        && payload.species != Species::Balloon
+        && !payload.species.is_solid();
+    let dx = if !carrying && api.once_in(4) {
+        api.rand_dir_2()
+    } else {
+        0
+    };
+    if api.get(dx, -1).species != Species::Empty {
+        if carrying {
+            // keep it from dropping away
+            api.set(0, 1, payload);
+        }
+        return;
+    }
+    if carrying {
+        api.set(0, 0, payload);
+        api.set(0, 1, EMPTY_CELL);
+    } else {
+        api.set(0, 0, EMPTY_CELL);
+    }
+    if hot && api.get(dx, -2).species == Species::Empty {
+        api.set(dx, -2, cell);
+    } else {
+        api.set(dx, -1, cell);
+    }
+}
+
+pub fn update_dynamite(cell: Cell, mut api: SandApi) {
+    // unlit, rb holds 100 + the blast direction, like the laser
+    if cell.rb < 100 {
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: 100 + join_dy_dx(1, 0),
+                ..cell
+            },
+        );
+        return;
+    }
+    if cell.rb < DYNAMITE_LIT {
+        let lit = NEIGHBORS.iter().any(|&(dx, dy)| {
+            let nbr = api.get(dx, dy).species;
+            nbr == Species::Fire || nbr == Species::Lava
+        });
+        if lit {
+            let rb = DYNAMITE_LIT + (cell.rb - 100) * DYNAMITE_FUSE + DYNAMITE_FUSE - 1;
+            api.set(0, 0, Cell { rb, ..cell });
+        }
+        return;
+    }
+    let fuse = (cell.rb - DYNAMITE_LIT) % DYNAMITE_FUSE;
+    if fuse > 0 {
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: cell.rb - 1,
+                ..cell
+            },
+        );
+        return;
+    }
+    let (dx, dy) = split_dy_dx((cell.rb - DYNAMITE_LIT) / DYNAMITE_FUSE);
+    api.set(0, 0, EMPTY_CELL);
+    // a cone widening away from the stick, clearing everything but wall and
+    // cloner and leaving a gust blowing out along it
+    let (px, py) = (-dy, dx);
+    for step in 1..(DYNAMITE_REACH + 1) {
+        let spread = step / 4;
+        for o in -spread..(spread + 1) {
+            let (x, y) = (dx * step + px * o, dy * step + py * o);
+            let (nx, ny) = (api.x + x, api.y + y);
+            if nx < 0 || nx > api.universe.width - 1 || ny < 0 || ny > api.universe.height - 1 {
+                continue;
+            }
+            let species = api.get_far(x, y).species;
+            if species == Species::Wall || species == Species::Cloner {
+                continue;
+            }
+            api.set_far(x, y, EMPTY_CELL);
+            // pressure falling off away from the stick, written into burns
+            // like a bomb's, so the fluid sim blows out along the cone
+            api.set_fluid_far(
+                x,
+                y,
+                Wind {
+                    dx: 0,
+                    dy: 0,
+                    pressure: (255 - step * 200 / DYNAMITE_REACH) as u8,
+                    density: 120,
+                },
+            );
+        }
+    }
+}
+
+pub fn update_glue(cell: Cell, mut api: SandApi) {
+    if cell.rb == GLUE_SET {
+        return;
+    }
+    // rb counts ticks spent pressed between two solid things
+    let mut held = 0;
+    for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().cloned() {
+        let nbr = api.get(dx, dy).species;
+        if nbr != Species::Empty && nbr != Species::Glue && !nbr.is_fluid() {
+            held += 1;
+        }
+    }
+    if held >= 2 {
+        let rb = if cell.rb + 1 >= GLUE_DRY {
+            GLUE_SET
+        } else {
+            cell.rb + 1
+        };
+        api.set(0, 0, Cell { rb, ..cell });
+        return;
+    }
+    // otherwise it's a slow, runny liquid
+    let dx = api.rand_dir_2();
+    if api.get(0, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, cell);
+    } else if api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, cell);
+    } else if api.once_in(3) && api.get(dx, 0).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 0, cell);
+    }
+}
+
+pub fn update_phosphorus(cell: Cell, mut api: SandApi) {
+    let mut air = 0;
+    let mut wet = None;
+    for (dx, dy) in NEIGHBORS.iter().cloned() {
+        match api.get(dx, dy).species {
+            Species::Empty => air += 1,
+            Species::Water => wet = Some((dx, dy)),
+            _ => {}
+        }
+    }
+    // sealed in, or kept under water, it's inert. once air gets at it, it
+    // lights by itself, and any water touching it flashes off as well.
+    if let Some((dx, dy)) = wet.filter(|_| air > 0) {
+        api.set(
+            dx,
+            dy,
+            Cell {
+                species: Species::Gas,
+                ra: cell.ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Fire,
+                ra: 220,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        api.set_fluid(Wind {
+            dx: 0,
+            dy: 0,
+            pressure: 70,
+            density: 40,
+        });
+        return;
+    }
+    if air >= PHOSPHORUS_AIR {
+        let ra = 180 + api.rand_int(60) as u8;
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Fire,
+                ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        return;
+    }
+    let dx = api.rand_dir_2();
+    if api.get(0, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, cell);
+    } else if api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, cell);
+    }
+}
+
+pub fn update_soil(cell: Cell, mut api: SandApi) {
+    // feeds the plant or grass touching it, growing it by a cell at the end
+    // of a run of it, at the cost of one of its nutrients (counted up in
+    // rb). spent soil is just sand.
+    if api.once_in(SOIL_FEED) {
+        let (dx, dy) = api.rand_vec_8();
+        let nbr = api.get(dx, dy);
+        if nbr.species == Species::Plant || nbr.species == Species::Grass {
+            let (gx, gy) = api.rand_vec_8();
+            let (mut tx, mut ty) = (dx + gx, dy + gy);
+            for _ in 0..SOIL_REACH {
+                if api.get_far(tx, ty).species != nbr.species {
+                    break;
+                }
+                tx += gx;
+                ty += gy;
+            }
+            let ground = api.get_far(tx, ty + 1).species;
+            if api.get_far(tx, ty).species == Species::Empty
+                && (nbr.species == Species::Plant
+                    || ground == Species::Soil
+                    || ground == Species::Sand)
+            {
+                let drift = (api.rand_int(100) % 11) - 5;
+                api.set_far(
+                    tx,
+                    ty,
+                    Cell {
+                        ra: (nbr.ra as i32 + drift) as u8,
+                        rb: 0,
+                        ..nbr
+                    },
+                );
+                let spent = cell.rb + 1;
+                let cell = if spent >= SOIL_NUTRIENTS {
+                    Cell {
+                        species: Species::Sand,
+                        rb: 0,
+                        ..cell
+                    }
+                } else {
+                    Cell { rb: spent, ..cell }
+                };
+                api.set(0, 0, cell);
+                return;
+            }
+        }
+    }
+    update_sand(cell, api);
+}
+
+pub fn update_confetti(cell: Cell, mut api: SandApi) {
+    // rb 0 is fresh from the brush: pick a colour. after that it counts
+    // ticks spent lying still, until it fades away
+    if cell.rb == 0 {
+        let ra = api.rand_int(256) as u8;
+        api.set(0, 0, Cell { ra, rb: 1, ..cell });
+        return;
+    }
+    let dx = api.rand_int(3) - 1;
+    let airborne = api.get(0, 1).species == Species::Empty;
+    if airborne && api.once_in(2) {
+        // hang in the air a moment
+        api.set(0, 0, cell);
+    } else if api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, Cell { rb: 1, ..cell });
+    } else if airborne {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, Cell { rb: 1, ..cell });
+    } else if cell.rb >= CONFETTI_LIFE {
+        api.set(0, 0, EMPTY_CELL);
+    } else {
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: cell.rb + 1,
+                ..cell
+            },
+        );
+    }
+}
+
+pub fn update_membrane(_cell: Cell, mut api: SandApi) {
+    // nothing gets through on its own; the membrane hands plain water across
+    // to an empty cell on the far side. water with anything dissolved in it
+    // is held back
+    if !api.once_in(MEMBRANE_SEEP) {
+        return;
+    }
+    let (dx, dy) = api.rand_vec_8();
+    let nbr = api.get(dx, dy);
+    if nbr.species != Species::Water || nbr.rb & SOLUTION != 0 {
+        return;
+    }
+    if api.get(-dx, -dy).species == Species::Empty {
+        api.set(dx, dy, EMPTY_CELL);
+        api.set(-dx, -dy, nbr);
+    }
+}
+
+pub fn update_frost(cell: Cell, mut api: SandApi) {
+    // rb is how many cells it has crept from the painted source, which is 0
+    for (dx, dy) in NEIGHBORS.iter().cloned() {
+        let nbr = api.get(dx, dy);
+        match nbr.species {
+            Species::Fire | Species::Lava => {
+                api.set(0, 0, EMPTY_CELL);
+                return;
+            }
+            Species::Water => {
+                api.set(
+                    dx,
+                    dy,
+                    Cell {
+                        species: Species::Ice,
+                        ..nbr
+                    },
+                );
+            }
+            // holding a grain back for a tick makes it crawl past
+            _ if nbr.species.is_powder() && api.once_in(2) => api.set(dx, dy, nbr),
+            _ => {}
+        }
+    }
+    if cell.rb >= FROST_REACH || !api.may_grow() || !api.once_in(FROST_GROWTH) {
+        return;
+    }
+    // creep into open air, but only along something to cling to
+    let (dx, dy) = api.rand_vec_8();
+    if api.get(dx, dy).species != Species::Empty {
+        return;
+    }
+    for (sx, sy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().cloned() {
+        if (dx + sx, dy + sy) == (0, 0) {
+            continue;
+        }
+        let surface = api.get(dx + sx, dy + sy).species;
+        if surface != Species::Empty && surface != Species::Frost {
+            api.set(
+                dx,
+                dy,
+                Cell {
+                    rb: cell.rb + 1,
+                    ..cell
+                },
+            );
+            return;
+        }
+    }
+}
+
+pub fn update_conveyor(cell: Cell, mut api: SandApi) {
+    // rb 0 runs right, 1 runs left. the whole belt is driven from its back
+    // end, shifting loads from the front backwards so each moves one cell
+    // per tick whichever way the grid is scanned
+    let dx = if cell.rb == 0 { 1 } else { -1 };
+    let same_belt = |c: Cell| c.species == Species::Conveyor && c.rb == cell.rb;
+    if same_belt(api.get(-dx, 0)) {
+        return;
+    }
+    let mut len = 1;
+    while len < CONVEYOR_REACH && same_belt(api.get_far(len * dx, 0)) {
+        len += 1;
+    }
+    let (mut wired, mut charged) = (false, false);
+    for n in 0..len {
+        let (w, c) = battery_power(&mut api, n * dx, 0);
+        wired |= w;
+        charged |= c;
+    }
+    if wired && !charged {
+        return;
+    }
+    for n in (0..len).rev() {
+        let load = api.get_far(n * dx, -1);
+        if !load.is_powder() && !load.is_fluid() {
+            continue;
+        }
+        // off the front end it just drops
+        if api.get_far((n + 1) * dx, -1).species == Species::Empty {
+            api.set_far(n * dx, -1, EMPTY_CELL);
+            api.set_far((n + 1) * dx, -1, load);
+        }
+    }
+}
+
+pub fn update_egg(cell: Cell, mut api: SandApi) {
+    // rb counts ticks since it was laid
+    let timer = cell.rb.saturating_add(1);
+    if timer >= api.universe.egg_hatch {
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Mite,
+                ra: 1,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        return;
+    }
+    let egg = Cell { rb: timer, ..cell };
+    if api.get(0, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, egg);
+    } else {
+        api.set(0, 0, egg);
+    }
+}
+
+pub fn update_spore(cell: Cell, mut api: SandApi) {
+    // rb counts ticks since release. spores hang on the air and leave the
+    // travelling to the wind, settling slowly until they find something
+    // fungus can grow on
+    let below = api.get(0, 1).species;
+    if matches!(
+        below,
+        Species::Wood | Species::Plant | Species::Soil | Species::Sawdust | Species::Leaf
+    ) {
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::Fungus,
+                ra: cell.ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        return;
+    }
+    if cell.rb >= SPORE_LIFE {
+        api.set(0, 0, EMPTY_CELL);
+        return;
+    }
+    let spore = Cell {
+        rb: cell.rb + 1,
+        ..cell
+    };
+    let dx = api.rand_dir();
+    if api.once_in(4) && api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, spore);
+    } else {
+        api.set(0, 0, spore);
+    }
+}
+
+pub fn update_needle(_cell: Cell, mut api: SandApi) {
+    // pops any balloon or bubble touching it, the balloon with the same
+    // puff of air as when fire gets it
+    for (dx, dy) in NEIGHBORS.iter().cloned() {
+        let (x, y) = (api.x + dx, api.y + dy);
+        if x < 0 || x > api.universe.width - 1 || y < 0 || y > api.universe.height - 1 {
+            continue;
+        }
+        match api.get(dx, dy).species {
+            Species::Balloon => {
+                api.set(dx, dy, EMPTY_CELL);
+                api.set_fluid(Wind {
+                    dx: 0,
+                    dy: 0,
+                    pressure: 40,
+                    density: 0,
+                });
+            }
+            Species::Bubble => api.set(dx, dy, EMPTY_CELL),
+            _ => {}
+        }
+    }
+}
+
+pub fn update_dirt(cell: Cell, mut api: SandApi) {
+    // rb 1 is packed: with DIRT_COMPACT cells piled on top it sets into a
+    // block that holds its shape, until what's under it gives way and it
+    // crumbles loose again
+    let below = api.get(0, 1);
+    if below.species != Species::Empty {
+        if cell.rb == 1 {
+            return;
+        }
+        let mut depth = 0;
+        while depth < DIRT_COMPACT && api.get_far(0, -1 - depth).species != Species::Empty {
+            depth += 1;
+        }
+        if depth == DIRT_COMPACT {
+            api.set(0, 0, Cell { rb: 1, ..cell });
+            return;
+        }
+    }
+    // loose, it falls like sand but heaps up steeper
+    let dirt = Cell { rb: 0, ..cell };
+    let dx = api.rand_dir_2();
+    if below.species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(0, 1, dirt);
+    } else if api.once_in(2) && api.get(dx, 1).species == Species::Empty {
+        api.set(0, 0, EMPTY_CELL);
+        api.set(dx, 1, dirt);
+    } else if below.species == Species::Water || below.species == Species::Oil {
+        api.set(0, 0, below);
+        api.set(0, 1, dirt);
+    } else {
+        api.set(0, 0, dirt);
+    }
+}
+
+pub fn update_quicklime(cell: Cell, mut api: SandApi) {
+    // soaks up a touching drop of water, giving off a burst of heat, and
+    // sets as slaked lime. otherwise it's just a powder
+    for (dx, dy) in NEIGHBORS.iter().cloned() {
+        if api.get(dx, dy).species != Species::Water {
+            continue;
+        }
+        api.set(dx, dy, EMPTY_CELL);
+        let (x, y) = (api.x, api.y);
+        api.universe.apply_heat_source(x, y, 1, QUICKLIME_HEAT);
+        api.set(
+            0,
+            0,
+            Cell {
+                species: Species::SlakedLime,
+                ..cell
+            },
+        );
+        return;
+    }
+    update_sand(cell, api);
+}
+
+pub fn update_mycelium(cell: Cell, mut api: SandApi) {
+    // threads its way through dirt, never out into the open, living off
+    // the soil it touches. rb is its store of nutrients: it draws them out
+    // of soil (which runs down to sand, as under a plant), passes them along
+    // the network and spends one on each cell of dirt it grows into. where
+    // it reaches the surface it now and then pushes up a fungus cap
+    if api.get(0, -1).species == Species::Empty && api.once_in(MYCELIUM_FRUIT) {
+        api.set(
+            0,
+            -1,
+            Cell {
+                species: Species::Fungus,
+                ra: cell.ra,
+                rb: 0,
+                clock: 0,
+            },
+        );
+        return;
+    }
+    if !api.once_in(MYCELIUM_GROWTH) {
+        return;
+    }
+    let (dx, dy) = api.rand_vec_8();
+    let nbr = api.get(dx, dy);
+    if nbr.species == Species::Soil && cell.rb < MYCELIUM_STORE {
+        let spent = nbr.rb + 1;
+        let soil = if spent >= SOIL_NUTRIENTS {
+            Cell {
+                species: Species::Sand,
+                rb: 0,
+                ..nbr
+            }
+        } else {
+            Cell { rb: spent, ..nbr }
+        };
+        api.set(dx, dy, soil);
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: cell.rb + 1,
+                ..cell
+            },
+        );
+    } else if nbr.species == Species::Mycelium && nbr.rb + 1 < cell.rb {
+        api.set(
+            dx,
+            dy,
+            Cell {
+                rb: nbr.rb + 1,
+                ..nbr
+            },
+        );
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: cell.rb - 1,
+                ..cell
+            },
+        );
+    } else if nbr.species == Species::Dirt && cell.rb > 0 && api.may_grow() {
+        let drift = (api.rand_int(100) % 11) - 5;
+        api.set(
+            dx,
+            dy,
+            Cell {
+                ra: (cell.ra as i32 + drift) as u8,
+                rb: 0,
+                ..cell
+            },
+        );
+        api.set(
+            0,
+            0,
+            Cell {
+                rb: cell.rb - 1,
+                ..cell
+            },
+        );
+    }
+}
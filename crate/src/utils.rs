@@ -1,5 +1,16 @@
 use cfg_if::cfg_if;
 
+pub const NEIGHBORS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
 pub fn adjacency_right(dir: (i32, i32)) -> (i32, i32) {
     match dir {
         (0, 1) => (1, 1),
@@ -40,6 +51,57 @@ pub fn split_dy_dx(s: u8) -> (i32, i32) {
     (dx, dy)
 }
 
+pub const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+// 64-bit FNV-1a, fed byte by byte so the result doesn't depend on endianness
+pub fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// url-safe base64 without padding, so share strings can sit in a link
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 4 / 3 + 2);
+    for chunk in bytes.chunks(3) {
+        let b1 = *chunk.get(1).unwrap_or(&0);
+        let b2 = *chunk.get(2).unwrap_or(&0);
+        let n = ((chunk[0] as u32) << 16) | ((b1 as u32) << 8) | (b2 as u32);
+        for i in 0..(chunk.len() + 1) {
+            out.push(BASE64_URL[((n >> (18 - 6 * i)) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+pub fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let mut n: u32 = 0;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'-' => 62,
+            b'_' => 63,
+            _ => return None,
+        };
+        n = (n << 6) | (v as u32);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((n >> bits) as u8);
+            n &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
 cfg_if! {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function at least once during initialization, and then
@@ -55,3 +117,37 @@ cfg_if! {
          pub fn set_panic_hook() {}
     }
 }
+
+cfg_if! {
+    // performance.now() in the browser, a monotonic clock everywhere else
+    if #[cfg(target_arch = "wasm32")] {
+        pub struct Stopwatch(f64);
+
+        impl Stopwatch {
+            fn now() -> f64 {
+                web_sys::window()
+                    .and_then(|w| w.performance())
+                    .map(|p| p.now())
+                    .unwrap_or(0.0)
+            }
+            pub fn start() -> Stopwatch {
+                Stopwatch(Stopwatch::now())
+            }
+            pub fn elapsed_micros(&self) -> f64 {
+                (Stopwatch::now() - self.0) * 1000.0
+            }
+        }
+    } else {
+        pub struct Stopwatch(std::time::Instant);
+
+        impl Stopwatch {
+            pub fn start() -> Stopwatch {
+                Stopwatch(std::time::Instant::now())
+            }
+            pub fn elapsed_micros(&self) -> f64 {
+                let elapsed = self.0.elapsed();
+                elapsed.as_secs() as f64 * 1e6 + elapsed.subsec_nanos() as f64 / 1e3
+            }
+        }
+    }
+}
@@ -2,12 +2,2471 @@
 
 #![cfg(target_arch = "wasm32")]
 
+extern crate sandtable;
 extern crate wasm_bindgen_test;
+use sandtable::{Cell, Preset, ScanOrder, Species, Universe, FORMAT_VERSION, MAX_UNDO};
 use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
+fn cells(u: &Universe) -> &[Cell] {
+    u.cells_slice()
+}
+
+fn species_at(u: &Universe, x: i32, y: i32) -> Species {
+    cells(u)[(x * u.height() + y) as usize].species()
+}
+
+fn count(u: &Universe, species: Species) -> usize {
+    cells(u).iter().filter(|c| c.species() == species).count()
+}
+
+fn fill(u: &mut Universe, x0: i32, y0: i32, x1: i32, y1: i32, species: Species) {
+    for x in x0..x1 {
+        for y in y0..y1 {
+            u.paint(x, y, 1, species);
+        }
+    }
+}
+
 #[wasm_bindgen_test]
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+#[wasm_bindgen_test]
+fn bomb_leaves_crater() {
+    let mut u = Universe::new(64, 64);
+    u.paint(32, 32, 1, Species::Bomb);
+    fill(&mut u, 0, 0, 64, 64, Species::Sand);
+    let before = count(&u, Species::Sand);
+
+    let mut ticks = 0;
+    while count(&u, Species::Bomb) > 0 && ticks < 200 {
+        u.tick();
+        ticks += 1;
+    }
+    assert!(ticks > 50, "fuse burned too fast: {}", ticks);
+    assert_eq!(count(&u, Species::Bomb), 0);
+
+    let mut hollow = 0;
+    for dx in -3..4 {
+        for dy in -3..4 {
+            if species_at(&u, 32 + dx, 32 + dy) == Species::Empty {
+                hollow += 1;
+            }
+        }
+    }
+    assert!(hollow > 30, "no crater: {}", hollow);
+    let after = count(&u, Species::Sand);
+    assert!(after > 0 && after < before - 60);
+}
+
+#[wasm_bindgen_test]
+fn bomb_crater_shows_in_changed_bounds() {
+    let mut u = Universe::new(64, 64);
+    u.paint(32, 32, 1, Species::Bomb);
+    fill(&mut u, 0, 0, 64, 64, Species::Sand);
+
+    let mut ticks = 0;
+    while count(&u, Species::Bomb) > 0 && ticks < 200 {
+        u.tick();
+        ticks += 1;
+    }
+    let b = u.changed_bounds();
+    assert_eq!(b.len(), 4);
+    assert!(
+        b[2] - b[0] > 8 && b[3] - b[1] > 8,
+        "crater not marked: {:?}",
+        b
+    );
+}
+
+#[wasm_bindgen_test]
+fn species_discriminants_are_stable() {
+    assert_eq!(FORMAT_VERSION, 1);
+    assert_eq!(Species::Empty as u8, 0);
+    assert_eq!(Species::Wall as u8, 1);
+    assert_eq!(Species::Sand as u8, 2);
+    assert_eq!(Species::Water as u8, 3);
+    assert_eq!(Species::Fire as u8, 6);
+    assert_eq!(Species::Rocket as u8, 17);
+    assert_eq!(Species::Bomb as u8, 20);
+}
+
+#[wasm_bindgen_test]
+fn touching_checks_neighborhood() {
+    let mut u = Universe::new(16, 16);
+    u.paint(5, 5, 1, Species::Stone);
+    assert!(!u.touching(5, 5, Species::Water));
+
+    u.paint(7, 5, 1, Species::Water);
+    assert!(!u.touching(5, 5, Species::Water));
+
+    u.paint(6, 6, 1, Species::Water);
+    assert!(u.touching(5, 5, Species::Water));
+    assert!(!u.touching(0, 0, Species::Water));
+}
+
+#[wasm_bindgen_test]
+fn vine_climbs_walls_only() {
+    let mut u = Universe::new(32, 32);
+    fill(&mut u, 10, 5, 11, 32, Species::Wall);
+    u.paint(11, 31, 1, Species::Vine);
+    for _ in 0..600 {
+        u.tick();
+    }
+    let climbed = (0..20).any(|y| species_at(&u, 11, y) == Species::Vine);
+    assert!(climbed);
+    for x in 14..32 {
+        for y in 0..30 {
+            assert_ne!(species_at(&u, x, y), Species::Vine, "vine in open air");
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn share_string_round_trips() {
+    let mut u = Universe::new(40, 30);
+    u.paint(10, 10, 6, Species::Sand);
+    u.paint(25, 20, 4, Species::Water);
+    fill(&mut u, 0, 28, 40, 30, Species::Wall);
+    let shared = u.to_share_string();
+    assert!(shared
+        .bytes()
+        .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+
+    let mut copy = Universe::new(40, 30);
+    copy.from_share_string(&shared).unwrap();
+    for x in 0..40 {
+        for y in 0..30 {
+            assert_eq!(species_at(&copy, x, y), species_at(&u, x, y));
+        }
+    }
+    assert_eq!(copy.to_share_string(), shared);
+}
+
+#[wasm_bindgen_test]
+fn share_string_rejects_unknown_species() {
+    // one run of a single cell: version, 1x1, then (count, species, ra, rb)
+    let mut u = Universe::new(1, 1);
+    assert_eq!(u.to_share_string(), "AQABAAEBAAAA");
+    // the same with species 200
+    assert!(u.from_share_string("AQABAAEByAAA").is_err());
+    assert_eq!(count(&u, Species::Empty), 1);
+}
+
+#[wasm_bindgen_test]
+fn pump_lifts_water_up_a_pipe() {
+    let mut u = Universe::new(20, 40);
+    fill(&mut u, 9, 5, 10, 31, Species::Wall);
+    fill(&mut u, 11, 5, 12, 31, Species::Wall);
+    u.paint(10, 30, 1, Species::Pump);
+    fill(&mut u, 0, 31, 20, 40, Species::Water);
+    for _ in 0..300 {
+        u.tick();
+    }
+    assert_eq!(species_at(&u, 10, 30), Species::Pump);
+    let risen = (5..20)
+        .filter(|&y| species_at(&u, 10, y) == Species::Water)
+        .count();
+    assert!(risen > 5, "only {} cells of water high in the pipe", risen);
+}
+
+#[wasm_bindgen_test]
+fn settle_drops_floating_sand() {
+    let mut u = Universe::new(20, 30);
+    fill(&mut u, 10, 5, 11, 15, Species::Sand);
+    u.paint(3, 10, 1, Species::Fire);
+    u.settle(100);
+    let mut low = 0;
+    for x in 0..20 {
+        for y in 0..30 {
+            if species_at(&u, x, y) == Species::Sand {
+                assert!(y >= 24, "sand still floating at {}, {}", x, y);
+                low += 1;
+            }
+        }
+    }
+    assert_eq!(low, 10);
+    assert_eq!(species_at(&u, 3, 10), Species::Fire);
+}
+
+#[wasm_bindgen_test]
+fn species_categories() {
+    assert!(Species::Wood.is_flammable());
+    assert!(Species::Oil.is_flammable());
+    assert!(!Species::Stone.is_flammable());
+    assert!(Species::Water.is_fluid());
+    assert!(Species::Lava.is_fluid());
+    assert!(!Species::Sand.is_fluid());
+    assert!(Species::Sand.is_powder());
+    assert!(!Species::Wall.is_powder());
+    assert!(Species::Wall.is_solid());
+    assert!(Species::Stone.is_solid());
+    assert!(!Species::Empty.is_solid());
+
+    let mut u = Universe::new(4, 4);
+    u.paint(1, 1, 1, Species::Oil);
+    let cell = cells(&u)[5];
+    assert!(cell.is_fluid() && cell.is_flammable() && !cell.is_solid());
+}
+
+#[wasm_bindgen_test]
+fn gradient_ramps_density() {
+    let mut u = Universe::new(100, 40);
+    u.paint_gradient(0, 0, 100, 40, Species::Dust, 0.05, 0.9);
+    let column_count = |u: &Universe, xs: std::ops::Range<i32>| {
+        xs.map(|x| {
+            (0..40)
+                .filter(|&y| species_at(u, x, y) == Species::Dust)
+                .count()
+        })
+        .sum::<usize>()
+    };
+    let sparse = column_count(&u, 0..20);
+    let dense = column_count(&u, 80..100);
+    assert!(dense > sparse * 3, "sparse {} dense {}", sparse, dense);
+}
+
+#[wasm_bindgen_test]
+fn noise_is_reproducible_and_thresholded() {
+    let noise = |threshold: f32| {
+        let mut u = Universe::new(80, 60);
+        u.paint_noise(5, 5, 75, 55, Species::Wall, threshold, 8.0);
+        u.species_mask(Species::Wall)
+    };
+    let walls = |mask: &[u8]| mask.iter().filter(|&&m| m != 0).count();
+    let low = noise(0.4);
+    assert_eq!(low, noise(0.4));
+    let high = noise(0.7);
+    assert!(walls(&high) > 0);
+    assert!(
+        walls(&high) < walls(&low),
+        "{} {}",
+        walls(&high),
+        walls(&low)
+    );
+    // nothing lands outside the region
+    assert!(low[..5 * 80].iter().all(|&m| m == 0));
+}
+
+#[wasm_bindgen_test]
+fn coral_grows_only_underwater() {
+    let mut u = Universe::new(40, 40);
+    u.paint(20, 39, 1, Species::Coral);
+    fill(&mut u, 0, 10, 40, 40, Species::Water);
+    for _ in 0..800 {
+        u.tick();
+    }
+    let mut grown = 0;
+    for x in 0..40 {
+        for y in 0..40 {
+            if species_at(&u, x, y) == Species::Coral {
+                assert!(y >= 10, "coral grew out of the water at {}, {}", x, y);
+                grown += 1;
+            }
+        }
+    }
+    assert!(grown > 5, "coral only reached {} cells", grown);
+
+    let mut dry = Universe::new(20, 20);
+    dry.paint(10, 19, 1, Species::Coral);
+    for _ in 0..400 {
+        dry.tick();
+    }
+    assert_eq!(count(&dry, Species::Coral), 1);
+}
+
+#[wasm_bindgen_test]
+fn benchmark_tick_reports_time() {
+    let mut u = Universe::new(200, 200);
+    fill(&mut u, 0, 100, 200, 200, Species::Sand);
+    u.paint(100, 50, 40, Species::Water);
+    let micros = u.benchmark_tick(20);
+    assert!(micros.is_finite() && micros > 0.0, "got {}", micros);
+}
+
+fn connected(u: &Universe, species: Species) -> bool {
+    let (w, h) = (u.width(), u.height());
+    let start = match (0..w * h).find(|&i| cells(u)[i as usize].species() == species) {
+        Some(i) => i,
+        None => return true,
+    };
+    let mut seen = vec![false; (w * h) as usize];
+    let mut stack = vec![(start / h, start % h)];
+    seen[start as usize] = true;
+    let mut reached = 0;
+    while let Some((x, y)) = stack.pop() {
+        reached += 1;
+        for dx in -1..2 {
+            for dy in -1..2 {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= w || ny >= h || seen[(nx * h + ny) as usize] {
+                    continue;
+                }
+                if species_at(u, nx, ny) == species {
+                    seen[(nx * h + ny) as usize] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+    }
+    reached == count(u, species)
+}
+
+#[wasm_bindgen_test]
+fn slime_drapes_over_peg() {
+    let mut u = Universe::new(40, 50);
+    fill(&mut u, 19, 25, 22, 26, Species::Wall);
+    u.paint(20, 12, 9, Species::Slime);
+    let blob = count(&u, Species::Slime);
+    for _ in 0..300 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Slime), blob);
+    assert!(connected(&u, Species::Slime), "slime broke apart");
+    let draped = (0..40).any(|x| (26..50).any(|y| species_at(&u, x, y) == Species::Slime));
+    assert!(draped);
+    assert!((19..22).any(|x| species_at(&u, x, 24) == Species::Slime));
+}
+
+#[wasm_bindgen_test]
+fn wind_reads_back() {
+    let mut u = Universe::new(10, 10);
+    assert_eq!(u.get_wind_at(4, 4), [0, 0]);
+    u.add_wind(4, 4, 30, -20);
+    assert_eq!(u.get_wind_at(4, 4), [30, -20]);
+    assert_eq!(u.get_wind_at(5, 4), [0, 0]);
+    assert_eq!(u.get_wind_at(-1, 40), [0, 0]);
+}
+
+#[wasm_bindgen_test]
+fn nanites_plateau_at_cap() {
+    let mut u = Universe::new(64, 64);
+    u.set_nanite_cap(500);
+    u.paint(32, 32, 1, Species::Nanite);
+    fill(&mut u, 0, 0, 64, 64, Species::Sand);
+    for _ in 0..300 {
+        u.tick();
+        assert!(count(&u, Species::Nanite) <= 500);
+    }
+    let settled = count(&u, Species::Nanite);
+    assert!(settled > 350, "only {} nanites", settled);
+}
+
+#[wasm_bindgen_test]
+fn undo_depth_tracks_history() {
+    let mut u = Universe::new(8, 8);
+    assert_eq!(u.undo_depth(), 0);
+    for _ in 0..3 {
+        u.push_undo();
+    }
+    assert_eq!(u.undo_depth(), 3);
+    assert_eq!(u.redo_depth(), 0);
+
+    u.paint(4, 4, 1, Species::Sand);
+    u.pop_undo();
+    assert_eq!(species_at(&u, 4, 4), Species::Empty);
+    assert_eq!(u.undo_depth(), 2);
+    assert_eq!(u.redo_depth(), 1);
+    u.pop_redo();
+    assert_eq!(species_at(&u, 4, 4), Species::Sand);
+    assert_eq!(u.undo_depth(), 3);
+    assert_eq!(u.redo_depth(), 0);
+
+    for _ in 0..MAX_UNDO + 10 {
+        u.push_undo();
+    }
+    assert_eq!(u.undo_depth(), MAX_UNDO);
+    u.flush_undos();
+    assert_eq!(u.undo_depth(), 0);
+}
+
+#[wasm_bindgen_test]
+fn puddles_evaporate_when_enabled() {
+    let mut wet = Universe::new(40, 20);
+    fill(&mut wet, 5, 17, 35, 20, Species::Water);
+    let puddle = count(&wet, Species::Water);
+    for _ in 0..200 {
+        wet.tick();
+    }
+    assert_eq!(count(&wet, Species::Water), puddle);
+
+    wet.set_evaporation(255);
+    for _ in 0..200 {
+        wet.tick();
+    }
+    assert!(count(&wet, Species::Water) < puddle / 2);
+}
+
+#[wasm_bindgen_test]
+fn region_checksum_spots_changes() {
+    let mut a = Universe::new(32, 32);
+    a.paint(8, 8, 5, Species::Sand);
+    a.paint(24, 24, 3, Species::Water);
+    let mut b = Universe::new(32, 32);
+    b.from_share_string(&a.to_share_string()).unwrap();
+
+    assert_eq!(
+        a.region_checksum(0, 0, 16, 16),
+        b.region_checksum(0, 0, 16, 16)
+    );
+    assert_ne!(
+        a.region_checksum(0, 0, 16, 16),
+        a.region_checksum(16, 16, 32, 32)
+    );
+
+    b.paint(20, 20, 1, Species::Stone);
+    assert_eq!(
+        a.region_checksum(0, 0, 16, 16),
+        b.region_checksum(0, 0, 16, 16)
+    );
+    assert_ne!(
+        a.region_checksum(16, 16, 32, 32),
+        b.region_checksum(16, 16, 32, 32)
+    );
+    assert_eq!(
+        a.region_checksum(-5, -5, 16, 16),
+        a.region_checksum(0, 0, 16, 16)
+    );
+}
+
+#[wasm_bindgen_test]
+fn firework_bursts_at_fuse_height() {
+    let mut u = Universe::new(40, 60);
+    u.set_firework(10, 16);
+    u.paint(20, 50, 1, Species::Firework);
+    let mut ticks = 0;
+    while count(&u, Species::Firework) > 0 {
+        u.tick();
+        ticks += 1;
+        assert!(ticks < 20);
+    }
+    assert_eq!(count(&u, Species::Fire), 16);
+    for x in 0..40 {
+        for y in 0..60 {
+            if species_at(&u, x, y) == Species::Fire {
+                let (dx, dy) = (x - 20, y - 40);
+                assert!(dx * dx + dy * dy <= 36, "spark at {}, {}", x, y);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn cells_slice_matches_pointer_view() {
+    let mut u = Universe::new(20, 20);
+    u.paint(10, 10, 3, Species::Wall);
+    let slice = u.cells_slice();
+    assert_eq!(slice.len(), 400);
+    let raw = unsafe { std::slice::from_raw_parts(u.cells(), 400) };
+    assert!(slice
+        .iter()
+        .zip(raw)
+        .all(|(a, b)| a.species() == b.species()));
+    let filled = slice
+        .iter()
+        .filter(|c| c.species() != Species::Empty)
+        .count();
+    assert_eq!(filled, count(&u, Species::Wall));
+    assert!(filled > 0);
+}
+
+#[wasm_bindgen_test]
+fn acid_fumes_only_when_enabled() {
+    for &fumes in &[false, true] {
+        let mut u = Universe::new(30, 30);
+        u.set_acid_fumes(fumes);
+        fill(&mut u, 0, 15, 30, 30, Species::Stone);
+        fill(&mut u, 5, 8, 25, 15, Species::Acid);
+        let mut saw_gas = false;
+        for _ in 0..60 {
+            u.tick();
+            saw_gas |= count(&u, Species::Gas) > 0;
+        }
+        assert_eq!(saw_gas, fumes);
+        assert!(count(&u, Species::Stone) < 15 * 30);
+    }
+}
+
+#[wasm_bindgen_test]
+fn tagged_sand_path_descends() {
+    let mut u = Universe::new(20, 40);
+    u.paint(10, 5, 1, Species::Sand);
+    u.tag_cell(10, 5);
+    for _ in 0..20 {
+        u.tick();
+    }
+    let path = u.tagged_path();
+    assert_eq!(path.len(), 42);
+    assert_eq!(&path[..2], &[10, 5]);
+    for step in path.chunks(2).collect::<Vec<_>>().windows(2) {
+        assert!(step[1][1] >= step[0][1]);
+    }
+    let last = &path[path.len() - 2..];
+    assert_eq!(species_at(&u, last[0], last[1]), Species::Sand);
+    assert!(last[1] > 20);
+}
+
+#[wasm_bindgen_test]
+fn clay_hardens_only_when_dry() {
+    let mut dry = Universe::new(20, 20);
+    fill(&mut dry, 8, 15, 12, 20, Species::Clay);
+    let mut wet = Universe::new(20, 20);
+    fill(&mut wet, 8, 15, 12, 20, Species::Clay);
+    fill(&mut wet, 0, 10, 20, 20, Species::Water);
+    for _ in 0..100 {
+        dry.tick();
+        wet.tick();
+    }
+    assert_eq!(count(&dry, Species::Stone), 0);
+    for _ in 0..150 {
+        dry.tick();
+        wet.tick();
+    }
+    assert_eq!(count(&dry, Species::Clay), 0);
+    assert_eq!(count(&dry, Species::Stone), 20);
+    assert_eq!(count(&wet, Species::Clay), 20);
+    assert_eq!(count(&wet, Species::Stone), 0);
+}
+
+#[wasm_bindgen_test]
+fn new_with_density_sprinkles() {
+    let u = Universe::new_with_density(40, 40, 0.0, 0.05);
+    assert_eq!(count(&u, Species::Sand), 0);
+    assert!(count(&u, Species::Seed) > 0);
+
+    let u = Universe::new_with_density(40, 40, 0.5, 0.0);
+    let sand = count(&u, Species::Sand);
+    assert!(sand > 600 && sand < 1000, "{} sand", sand);
+    assert_eq!(count(&u, Species::Seed), 0);
+    assert_eq!(count(&Universe::new(40, 40), Species::Sand), 0);
+}
+
+#[wasm_bindgen_test]
+fn laser_bounces_off_mirror() {
+    let mut u = Universe::new(40, 40);
+    u.paint(5, 20, 1, Species::Laser);
+    u.aim(5, 20, 1, 0);
+    u.paint(20, 20, 1, Species::Mirror);
+    u.set_mirror(20, 20, false);
+    // one target up the reflected path, one straight ahead behind the mirror
+    u.paint(20, 5, 1, Species::Wood);
+    u.paint(30, 20, 1, Species::Wood);
+    u.tick();
+    assert_eq!(species_at(&u, 20, 5), Species::Fire);
+    assert_eq!(species_at(&u, 30, 20), Species::Wood);
+
+    u.paint(20, 5, 1, Species::Empty);
+    u.paint(20, 35, 1, Species::Wood);
+    u.set_mirror(20, 20, true);
+    u.tick();
+    assert_eq!(species_at(&u, 20, 35), Species::Fire);
+    assert_eq!(species_at(&u, 30, 20), Species::Wood);
+}
+
+#[wasm_bindgen_test]
+fn diff_stats_counts_a_falling_grain() {
+    let mut u = Universe::new(20, 20);
+    u.paint(10, 5, 1, Species::Sand);
+    let before = u.cells_slice().to_vec();
+    u.tick();
+    assert_eq!(species_at(&u, 10, 6), Species::Sand);
+    assert_eq!(u.diff_stats(&before), [0, 0, 1]);
+
+    let before = u.cells_slice().to_vec();
+    u.paint(3, 3, 1, Species::Wall);
+    u.paint(10, 6, 1, Species::Empty);
+    assert_eq!(u.diff_stats(&before), [1, 1, 0]);
+}
+
+#[wasm_bindgen_test]
+fn leaves_fall_and_rot() {
+    let mut u = Universe::new(20, 40);
+    fill(&mut u, 10, 5, 11, 30, Species::Plant);
+    let mut most = 0;
+    for _ in 0..600 {
+        u.tick();
+        most = most.max(count(&u, Species::Leaf));
+    }
+    assert!(most > 0);
+    assert!(most < 40, "{} leaves at once", most);
+    // with the plant gone, roots and all, the litter clears up on its own
+    u.erase_species(Species::Plant);
+    for _ in 0..250 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Leaf), 0);
+}
+
+#[wasm_bindgen_test]
+fn flat_paint_has_uniform_ra() {
+    let mut u = Universe::new(30, 30);
+    u.set_paint_randomness(0);
+    u.paint(10, 10, 7, Species::Wall);
+    u.paint(22, 20, 3, Species::Sand);
+    let painted: Vec<u8> = cells(&u)
+        .iter()
+        .filter(|c| c.species() != Species::Empty)
+        .map(|c| c.ra())
+        .collect();
+    assert!(painted.len() > 20);
+    assert!(painted.iter().all(|&ra| ra == 128));
+}
+
+#[wasm_bindgen_test]
+fn only_explosive_gas_blasts() {
+    for &explosive in &[false, true] {
+        let mut u = Universe::new(30, 30);
+        u.set_gas_behavior(0, explosive);
+        u.paint(15, 15, 1, Species::Fire);
+        fill(&mut u, 5, 5, 25, 25, Species::Gas);
+        let mut blast = false;
+        for _ in 0..10 {
+            u.tick();
+            blast |= u.burns_slice().iter().any(|b| b.pressure() >= 80);
+        }
+        assert_eq!(blast, explosive);
+        assert!(
+            count(&u, Species::Fire) > 1,
+            "{} {} {}",
+            explosive,
+            count(&u, Species::Fire),
+            count(&u, Species::Gas)
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+fn buoyant_gas_rises() {
+    let mut risen = [0; 2];
+    for (i, &rise) in [0u8, 200].iter().enumerate() {
+        let mut u = Universe::new(20, 40);
+        u.set_gas_behavior(rise, false);
+        fill(&mut u, 5, 30, 15, 35, Species::Gas);
+        for _ in 0..40 {
+            u.tick();
+        }
+        risen[i] = (0..20)
+            .flat_map(|x| (0..20).map(move |y| (x, y)))
+            .filter(|&(x, y)| species_at(&u, x, y) == Species::Gas)
+            .count();
+    }
+    assert!(risen[1] > risen[0], "{:?}", risen);
+}
+
+#[wasm_bindgen_test]
+fn find_species_scans_rows() {
+    let mut u = Universe::new(20, 20);
+    assert_eq!(u.find_species(Species::Lava), None);
+    u.paint(13, 7, 1, Species::Lava);
+    assert_eq!(u.find_species(Species::Lava), Some([13, 7]));
+    // a later row loses to an earlier one even when it's further left
+    u.paint(2, 12, 1, Species::Wall);
+    u.paint(18, 4, 1, Species::Wall);
+    assert_eq!(u.find_species(Species::Wall), Some([18, 4]));
+}
+
+#[wasm_bindgen_test]
+fn bubble_rises_and_pops() {
+    let mut u = Universe::new(12, 40);
+    fill(&mut u, 3, 5, 4, 40, Species::Wall);
+    fill(&mut u, 8, 5, 9, 40, Species::Wall);
+    u.paint(6, 38, 1, Species::Bubble);
+    fill(&mut u, 4, 10, 8, 40, Species::Water);
+    let water = count(&u, Species::Water);
+    let mut last_y = 38;
+    while let Some([_, y]) = u.find_species(Species::Bubble) {
+        assert!(y <= last_y);
+        last_y = y;
+        u.tick();
+    }
+    assert!(last_y <= 11, "popped at {}", last_y);
+    assert_eq!(count(&u, Species::Water), water);
+}
+
+#[wasm_bindgen_test]
+fn pressurised_paint_spreads_wider() {
+    let spread = |pressure: u8| {
+        let mut u = Universe::new(40, 40);
+        u.paint_pressure(20, 20, 5, Species::Water, pressure);
+        u.tick();
+        let xs: Vec<i32> = (0..40)
+            .filter(|&x| (0..40).any(|y| species_at(&u, x, y) == Species::Water))
+            .collect();
+        xs[xs.len() - 1] - xs[0]
+    };
+    assert!(spread(200) > spread(0));
+}
+
+#[wasm_bindgen_test]
+fn dust_cloud_explodes_when_enabled() {
+    let mut left = [0; 2];
+    for (i, &explosive) in [false, true].iter().enumerate() {
+        let mut u = Universe::new(40, 60);
+        u.set_dust_explosive(explosive);
+        u.paint(20, 21, 1, Species::Fire);
+        fill(&mut u, 10, 10, 30, 20, Species::Dust);
+        for _ in 0..4 {
+            u.tick();
+        }
+        left[i] = count(&u, Species::Dust);
+    }
+    assert!(left[0] > 150, "{:?}", left);
+    assert!(left[1] < left[0] / 4, "{:?}", left);
+}
+
+#[wasm_bindgen_test]
+fn column_heights_profile_terrain() {
+    let mut u = Universe::new(6, 20);
+    // walls don't count as terrain, whatever sits on them does
+    fill(&mut u, 0, 5, 6, 6, Species::Wall);
+    fill(&mut u, 1, 15, 2, 20, Species::Stone);
+    fill(&mut u, 2, 10, 3, 20, Species::Stone);
+    fill(&mut u, 3, 4, 4, 5, Species::Sand);
+    assert_eq!(u.column_heights(), vec![-1, 15, 10, 4, -1, -1]);
+}
+
+#[wasm_bindgen_test]
+fn acid_spares_resistant_species() {
+    let mut u = Universe::new(30, 30);
+    u.set_acid_resistant(Species::Wood, true);
+    // a wooden tub of acid, with a stone block in the middle of it
+    fill(&mut u, 5, 10, 6, 25, Species::Wood);
+    fill(&mut u, 24, 10, 25, 25, Species::Wood);
+    fill(&mut u, 5, 25, 25, 26, Species::Wood);
+    fill(&mut u, 13, 20, 17, 25, Species::Stone);
+    fill(&mut u, 6, 12, 24, 25, Species::Acid);
+    let wood = count(&u, Species::Wood);
+    for _ in 0..100 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Wood), wood);
+    assert!(count(&u, Species::Stone) < 20);
+}
+
+#[wasm_bindgen_test]
+fn water_tank_preset_holds_water() {
+    let mut u = Universe::new(40, 40);
+    u.paint(3, 3, 3, Species::Sand);
+    u.apply_preset(Preset::WaterTank);
+    assert_eq!(count(&u, Species::Sand), 0);
+    assert!(count(&u, Species::Water) > 100);
+    let water = count(&u, Species::Water);
+    for _ in 0..50 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Water), water);
+    // every water cell sits between walls on its row, above a wall floor
+    for x in 0..40 {
+        for y in 0..40 {
+            if species_at(&u, x, y) == Species::Water {
+                assert!((0..x).any(|wx| species_at(&u, wx, y) == Species::Wall));
+                assert!((x..40).any(|wx| species_at(&u, wx, y) == Species::Wall));
+                assert!((y..40).any(|wy| species_at(&u, x, wy) == Species::Wall));
+            }
+        }
+    }
+    for &preset in &[
+        Preset::EmptyWithFloor,
+        Preset::LavaLamp,
+        Preset::SandHourglass,
+    ] {
+        u.apply_preset(preset);
+        assert!(count(&u, Species::Wall) > 0);
+    }
+}
+
+#[wasm_bindgen_test]
+fn charge_fires_once_per_four_pulses() {
+    let mut u = Universe::new(20, 20);
+    u.paint(10, 10, 1, Species::Charge);
+    let mut fired = 0;
+    for t in 0..36 {
+        if t % 3 == 0 {
+            u.paint(10, 9, 1, Species::Electric);
+        }
+        u.tick();
+        if species_at(&u, 10, 11) == Species::Electric {
+            fired += 1;
+        }
+    }
+    assert_eq!(fired, 3);
+    assert_eq!(species_at(&u, 10, 10), Species::Charge);
+}
+
+#[wasm_bindgen_test]
+fn cloned_state_is_detached() {
+    let mut u = Universe::new(30, 30);
+    fill(&mut u, 5, 5, 25, 10, Species::Sand);
+    u.paint(15, 20, 4, Species::Water);
+    let before = u.region_checksum(0, 0, 30, 30);
+    let mut a = u.clone_state();
+    let mut b = u.clone_state();
+    for _ in 0..20 {
+        a.tick();
+        b.tick();
+    }
+    assert_eq!(u.region_checksum(0, 0, 30, 30), before);
+    assert_ne!(a.region_checksum(0, 0, 30, 30), before);
+    assert_eq!(
+        a.region_checksum(0, 0, 30, 30),
+        b.region_checksum(0, 0, 30, 30)
+    );
+}
+
+#[wasm_bindgen_test]
+fn web_slows_falling_sand() {
+    let fall_time = |webbed: bool| {
+        let mut u = Universe::new(10, 40);
+        if webbed {
+            fill(&mut u, 0, 15, 10, 20, Species::Web);
+        }
+        u.paint(5, 2, 1, Species::Sand);
+        let mut ticks = 0;
+        while species_at(&u, 5, 39) != Species::Sand {
+            u.tick();
+            ticks += 1;
+            assert!(ticks < 1000);
+        }
+        ticks
+    };
+    let open = fall_time(false);
+    assert!(fall_time(true) > open + 5);
+}
+
+#[wasm_bindgen_test]
+fn web_down_to_a_web_boundary_holds() {
+    let mut u = Universe::new(10, 20);
+    u.set_boundary(Species::Web);
+    fill(&mut u, 0, 10, 10, 20, Species::Web);
+    u.paint(5, 9, 1, Species::Sand);
+    for _ in 0..50 {
+        u.tick();
+    }
+    // nowhere to let it through to, so it stays on top
+    assert_eq!(species_at(&u, 5, 9), Species::Sand);
+}
+
+#[wasm_bindgen_test]
+fn tick_hook_sees_each_pass() {
+    let mut u = Universe::new(20, 20);
+    u.paint(10, 5, 1, Species::Sand);
+    u.add_wind(10, 5, 100, 0);
+    let start = u.cells_slice().to_vec();
+    let mut frames = Vec::new();
+    u.tick_with_hook(|u| frames.push(u.cells_slice().to_vec()));
+    assert_eq!(frames.len(), 2);
+    let changes = |a: &[Cell], b: &[Cell]| {
+        a.iter()
+            .zip(b)
+            .filter(|(p, q)| p.species() != q.species())
+            .count()
+    };
+    // blown one cell right by the wind pass, then dropped by the update pass
+    assert_eq!(frames[0][11 * 20 + 5].species(), Species::Sand);
+    assert_eq!(changes(&start, &frames[0]), 2);
+    assert_eq!(changes(&frames[0], &frames[1]), 2);
+    assert_eq!(species_at(&u, 11, 6), Species::Sand);
+    assert_eq!(u.total_ticks(), 1);
+
+    // a hooked tick is recorded like any other, and each only once
+    u.start_recording();
+    u.tick_with_hook(|_| {});
+    u.tick();
+    assert_eq!(u.export_replay().len(), 4 + 2 * 2);
+}
+
+#[wasm_bindgen_test]
+fn rubber_pushes_back_on_flung_sand() {
+    // pressure left in burns at the face of the backstop after flinging
+    // sand right at it; the fluid sim turns that into the bounce
+    let push = |backstop: Species| {
+        let mut u = Universe::new(40, 20);
+        fill(&mut u, 0, 15, 40, 20, Species::Wall);
+        fill(&mut u, 25, 0, 26, 15, backstop);
+        u.paint_with_velocity(20, 11, 9, Species::Sand, 100, 0);
+        u.tick();
+        (0..15)
+            .map(|y| u.burns_slice()[24 * 20 + y].pressure() as u32)
+            .sum::<u32>()
+    };
+    assert_eq!(push(Species::Wall), 0);
+    assert!(push(Species::Rubber) > 0);
+}
+
+#[wasm_bindgen_test]
+fn open_boundary_lets_sand_fall_out() {
+    let mut u = Universe::new(10, 10);
+    u.set_boundary(Species::Empty);
+    fill(&mut u, 3, 7, 6, 10, Species::Sand);
+    for _ in 0..5 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Sand), 0);
+
+    let mut walled = Universe::new(10, 10);
+    fill(&mut walled, 3, 7, 6, 10, Species::Sand);
+    for _ in 0..5 {
+        walled.tick();
+    }
+    assert_eq!(count(&walled, Species::Sand), 9);
+}
+
+#[wasm_bindgen_test]
+fn geyser_erupts_on_schedule() {
+    let mut u = Universe::new(40, 40);
+    u.set_geyser_period(25);
+    u.paint(20, 39, 1, Species::Geyser);
+    let mut eruptions = Vec::new();
+    let mut water = 0;
+    for t in 0..100 {
+        u.tick();
+        let now = count(&u, Species::Water);
+        if now > water {
+            eruptions.push(t);
+        }
+        water = now;
+    }
+    assert_eq!(eruptions.len(), 4, "{:?}", eruptions);
+    for pair in eruptions.windows(2) {
+        assert_eq!(pair[1] - pair[0], 25);
+    }
+}
+
+#[wasm_bindgen_test]
+fn vortex_circulates() {
+    let mut u = Universe::new(40, 40);
+    u.apply_vortex(20, 20, 100.0, 10);
+    let right = u.get_wind_at(25, 20);
+    let left = u.get_wind_at(15, 20);
+    let below = u.get_wind_at(20, 25);
+    let above = u.get_wind_at(20, 15);
+    assert!(right[1] > 0 && left[1] < 0);
+    assert!(below[0] < 0 && above[0] > 0);
+    assert_eq!(right[0], 0);
+    assert_eq!(u.get_wind_at(35, 35), [0, 0]);
+
+    let mut u = Universe::new(10, 10);
+    u.apply_uniform_wind(3.0, 4.0, 50.0);
+    assert_eq!(u.get_wind_at(0, 0), [30, 40]);
+    assert_eq!(u.get_wind_at(9, 9), [30, 40]);
+}
+
+#[wasm_bindgen_test]
+fn wet_sawdust_resists_fire() {
+    // ticks of steady flame until half the pile is gone
+    let burn_time = |wet: bool| {
+        let mut u = Universe::new(20, 20);
+        fill(&mut u, 0, 18, 20, 20, Species::Wall);
+        fill(&mut u, 4, 10, 5, 18, Species::Wall);
+        fill(&mut u, 15, 10, 16, 18, Species::Wall);
+        if wet {
+            // alternate layers so every grain has water to soak up
+            for y in 12..18 {
+                let layer = if y % 2 == 0 {
+                    Species::Sawdust
+                } else {
+                    Species::Water
+                };
+                fill(&mut u, 5, y, 15, y + 1, layer);
+            }
+            for _ in 0..40 {
+                u.tick();
+            }
+            let paste = cells(&u)
+                .iter()
+                .filter(|c| c.species() == Species::Sawdust && c.rb() != 0)
+                .count();
+            assert_eq!(paste, 30);
+        } else {
+            fill(&mut u, 5, 15, 15, 18, Species::Sawdust);
+        }
+        let pile = count(&u, Species::Sawdust);
+        assert_eq!(pile, 30);
+        let mut ticks = 0;
+        while count(&u, Species::Sawdust) > pile / 2 {
+            fill(&mut u, 5, 10, 15, 11, Species::Fire);
+            u.tick();
+            ticks += 1;
+            assert!(ticks < 2000);
+        }
+        ticks
+    };
+    let dry = burn_time(false);
+    let wet = burn_time(true);
+    assert!(wet > dry * 3, "wet {} dry {}", wet, dry);
+}
+
+#[wasm_bindgen_test]
+fn cells_equal_ignores_clocks() {
+    let mut u = Universe::new(20, 20);
+    for _ in 0..3 {
+        u.tick();
+    }
+    fill(&mut u, 2, 2, 8, 8, Species::Wall);
+    // decoding stamps the receiver's own generation into every clock
+    let mut v = Universe::new(20, 20);
+    v.from_share_string(&u.to_share_string()).unwrap();
+    assert!(u.cells_equal(&v));
+    v.paint(15, 15, 1, Species::Wall);
+    assert!(!u.cells_equal(&v));
+    assert!(!u.cells_equal(&Universe::new(20, 21)));
+}
+
+#[wasm_bindgen_test]
+fn grass_creeps_along_sand() {
+    let mut u = Universe::new(40, 30);
+    fill(&mut u, 0, 20, 40, 30, Species::Sand);
+    u.paint(20, 19, 1, Species::Grass);
+    for _ in 0..800 {
+        u.tick();
+    }
+    let grass = count(&u, Species::Grass);
+    assert!(grass > 10, "{} grass", grass);
+    for x in 0..40 {
+        for y in 0..30 {
+            if species_at(&u, x, y) == Species::Grass {
+                assert_eq!(species_at(&u, x, y + 1), Species::Sand);
+                assert_eq!(y, 19);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn memory_usage_tracks_history() {
+    let mut u = Universe::new(50, 50);
+    let base = u.memory_usage();
+    // cells, winds, burns and the dirty base, plus changed, light and temps
+    assert!(base >= 50 * 50 * (4 * 4 + 1 + 1 + 2));
+    for _ in 0..5 {
+        u.push_undo();
+    }
+    let with_history = u.memory_usage();
+    assert!(with_history >= base + 5 * 50 * 50 * 4);
+    u.flush_undos();
+    assert!(u.memory_usage() < with_history);
+
+    let idle = u.memory_usage();
+    u.start_recording();
+    for x in 0..50 {
+        u.paint(x, 10, 1, Species::Sand);
+    }
+    assert!(u.memory_usage() > idle);
+}
+
+#[wasm_bindgen_test]
+fn honey_drips_slower_than_water() {
+    // lowest point the stream reaches after pouring from a spout
+    let reach = |species: Species| {
+        let mut u = Universe::new(20, 60);
+        for _ in 0..30 {
+            u.paint(10, 2, 1, species);
+            u.tick();
+        }
+        (0..60)
+            .filter(|&y| (0..20).any(|x| species_at(&u, x, y) == species))
+            .max()
+            .unwrap()
+    };
+    let water = reach(Species::Water);
+    let honey = reach(Species::Honey);
+    assert!(water > 25);
+    assert!(honey < water / 2, "honey {} water {}", honey, water);
+}
+
+#[wasm_bindgen_test]
+fn ellipse_extent() {
+    let mut u = Universe::new(40, 40);
+    u.paint_ellipse(20, 20, 10, 4, Species::Wall);
+    let xs: Vec<i32> = (0..40)
+        .filter(|&x| (0..40).any(|y| species_at(&u, x, y) == Species::Wall))
+        .collect();
+    let ys: Vec<i32> = (0..40)
+        .filter(|&y| (0..40).any(|x| species_at(&u, x, y) == Species::Wall))
+        .collect();
+    assert_eq!((xs[0], xs[xs.len() - 1]), (10, 30));
+    assert_eq!((ys[0], ys[ys.len() - 1]), (16, 24));
+    assert_eq!(species_at(&u, 27, 22), Species::Wall);
+    assert_eq!(species_at(&u, 29, 23), Species::Empty);
+
+    let mut u = Universe::new(40, 40);
+    u.paint_ellipse(5, 5, 0, 3, Species::Wall);
+    assert_eq!(count(&u, Species::Wall), 7);
+    u.paint_ellipse(30, 30, 0, 0, Species::Sand);
+    assert_eq!(count(&u, Species::Sand), 1);
+}
+
+#[wasm_bindgen_test]
+fn lone_lava_cools_to_stone() {
+    let mut u = Universe::new(30, 30);
+    u.set_lava_cooling(10);
+    // a lone drop in a one-cell pocket, and a pool in a tub
+    u.paint(4, 29, 1, Species::Wall);
+    u.paint(6, 29, 1, Species::Wall);
+    u.paint(5, 29, 1, Species::Lava);
+    fill(&mut u, 10, 20, 11, 30, Species::Wall);
+    fill(&mut u, 20, 20, 21, 30, Species::Wall);
+    fill(&mut u, 11, 25, 20, 30, Species::Lava);
+    let pool = count(&u, Species::Lava) - 1;
+    for _ in 0..19 {
+        u.tick();
+    }
+    assert_eq!(species_at(&u, 5, 29), Species::Lava);
+    u.tick();
+    assert_eq!(species_at(&u, 5, 29), Species::Stone);
+    for _ in 0..100 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Lava), pool);
+}
+
+#[wasm_bindgen_test]
+fn swap_species_exchanges_counts() {
+    let mut u = Universe::new(30, 30);
+    fill(&mut u, 0, 0, 10, 5, Species::Sand);
+    fill(&mut u, 0, 20, 30, 25, Species::Water);
+    u.paint(25, 5, 3, Species::Wall);
+    let walls = count(&u, Species::Wall);
+    u.swap_species(Species::Sand, Species::Water);
+    assert_eq!(count(&u, Species::Sand), 150);
+    assert_eq!(count(&u, Species::Water), 50);
+    assert_eq!(count(&u, Species::Wall), walls);
+    assert_eq!(species_at(&u, 3, 3), Species::Water);
+}
+
+#[wasm_bindgen_test]
+fn moss_needs_damp_stone() {
+    let mut u = Universe::new(40, 30);
+    fill(&mut u, 0, 25, 40, 30, Species::Stone);
+    // a puddle held between walls near the left end
+    fill(&mut u, 2, 21, 3, 25, Species::Wall);
+    fill(&mut u, 8, 21, 9, 25, Species::Wall);
+    fill(&mut u, 3, 22, 8, 25, Species::Water);
+    u.paint(10, 24, 1, Species::Moss);
+    u.paint(32, 24, 1, Species::Moss);
+    for _ in 0..400 {
+        u.tick();
+    }
+    let near = |x0: i32, x1: i32| {
+        (x0..x1)
+            .flat_map(|x| (0..25).map(move |y| (x, y)))
+            .filter(|&(x, y)| species_at(&u, x, y) == Species::Moss)
+            .count()
+    };
+    assert!(near(0, 20) > 2, "{} wet moss", near(0, 20));
+    assert_eq!(near(20, 40), 1);
+}
+
+#[wasm_bindgen_test]
+fn species_mask_marks_matches() {
+    let mut u = Universe::new(20, 10);
+    let spots = [(0, 0), (19, 0), (3, 7), (12, 9)];
+    for &(x, y) in spots.iter() {
+        u.paint(x, y, 1, Species::Water);
+    }
+    u.paint(5, 5, 1, Species::Sand);
+    let mask = u.species_mask(Species::Water);
+    assert_eq!(mask.len(), 200);
+    for y in 0..10 {
+        for x in 0..20 {
+            let expected = spots.contains(&(x, y)) as u8;
+            assert_eq!(mask[(y * 20 + x) as usize], expected, "at {}, {}", x, y);
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn oil_spreads_into_a_slick() {
+    let mut u = Universe::new(60, 30);
+    fill(&mut u, 0, 25, 60, 30, Species::Water);
+    fill(&mut u, 27, 14, 32, 18, Species::Oil);
+    for _ in 0..300 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Oil), 20);
+    for x in 0..60 {
+        let column = (0..30)
+            .filter(|&y| species_at(&u, x, y) == Species::Oil)
+            .collect::<Vec<_>>();
+        assert!(column.len() <= 1, "column {} has {:?}", x, column);
+        if let Some(&y) = column.first() {
+            assert_eq!(y, 24);
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn count_in_region_only_counts_the_zone() {
+    let mut u = Universe::new(20, 20);
+    fill(&mut u, 0, 10, 20, 12, Species::Water);
+    u.paint(15, 15, 1, Species::Sand);
+    assert_eq!(u.count_in_region(5, 0, 10, 11, Species::Water), 5);
+    assert_eq!(u.count_in_region(-5, -5, 100, 100, Species::Water), 40);
+    assert_eq!(u.count_in_region(10, 10, 5, 12, Species::Water), 0);
+    assert_eq!(u.count_in_region(15, 15, 16, 16, Species::Sand), 1);
+}
+
+#[wasm_bindgen_test]
+fn seeds_wait_for_water() {
+    let pocket = |watered: bool| {
+        let mut u = Universe::new(20, 20);
+        u.set_seed_rules(true, true, 10);
+        fill(&mut u, 0, 15, 20, 20, Species::Sand);
+        fill(&mut u, 9, 10, 10, 15, Species::Wall);
+        fill(&mut u, 11, 10, 12, 15, Species::Wall);
+        u.paint(10, 14, 1, Species::Seed);
+        if watered {
+            u.paint(10, 13, 1, Species::Water);
+        }
+        u
+    };
+    let rb = |u: &Universe| cells(u)[10 * 20 + 14].rb();
+
+    let mut dry = pocket(false);
+    for _ in 0..100 {
+        dry.tick();
+    }
+    assert_eq!(species_at(&dry, 10, 14), Species::Seed);
+    assert_eq!(rb(&dry), 0);
+
+    let mut wet = pocket(true);
+    for _ in 0..9 {
+        wet.tick();
+    }
+    assert_eq!(rb(&wet), 254);
+    wet.tick();
+    assert!(rb(&wet) > 0 && rb(&wet) < 254);
+}
+
+#[wasm_bindgen_test]
+fn shift_scrolls_and_fills() {
+    let mut u = Universe::new(20, 10);
+    fill(&mut u, 4, 4, 7, 6, Species::Sand);
+    fill(&mut u, 17, 0, 20, 10, Species::Stone);
+    u.add_wind(5, 5, 20, 0);
+    u.shift(3, 0, Species::Wall);
+    assert_eq!(count(&u, Species::Sand), 6);
+    assert_eq!(u.find_species(Species::Sand), Some([7, 4]));
+    assert_eq!(species_at(&u, 9, 5), Species::Sand);
+    assert_eq!(species_at(&u, 6, 5), Species::Empty);
+    assert_eq!(u.get_wind_at(8, 5), [20, 0]);
+    // the left strip is new wall, the stone on the right fell off
+    for y in 0..10 {
+        for x in 0..3 {
+            assert_eq!(species_at(&u, x, y), Species::Wall);
+        }
+    }
+    assert_eq!(count(&u, Species::Wall), 30);
+    assert_eq!(count(&u, Species::Stone), 0);
+    // the temperature field scrolls along and the move shows as changed
+    let mut u = Universe::new(20, 10);
+    u.paint(5, 5, 1, Species::Sand);
+    u.apply_heat_source(5, 5, 0, 80);
+    u.shift(0, 2, Species::Empty);
+    assert_eq!(u.temps_slice()[5 * 10 + 7], 100);
+    assert_eq!(u.temps_slice()[5 * 10 + 5], 20);
+    assert_eq!(u.changed_bounds(), vec![5, 5, 6, 8]);
+}
+
+#[wasm_bindgen_test]
+fn crystal_grows_only_in_solution() {
+    let tank = |mineral: bool| {
+        let mut u = Universe::new(30, 30);
+        fill(&mut u, 0, 28, 30, 30, Species::Wall);
+        fill(&mut u, 0, 0, 1, 28, Species::Wall);
+        fill(&mut u, 29, 0, 30, 28, Species::Wall);
+        u.paint(15, 27, 1, Species::Crystal);
+        fill(&mut u, 1, 10, 29, 28, Species::Water);
+        if mineral {
+            u.dissolve(15, 20, 30);
+        }
+        for _ in 0..400 {
+            u.tick();
+        }
+        u
+    };
+    assert_eq!(count(&tank(false), Species::Crystal), 1);
+    let grown = tank(true);
+    assert!(
+        count(&grown, Species::Crystal) > 10,
+        "{} crystal",
+        count(&grown, Species::Crystal)
+    );
+    assert_eq!(
+        count(&grown, Species::Crystal) + count(&grown, Species::Water),
+        28 * 18
+    );
+}
+
+#[wasm_bindgen_test]
+fn tick_order_changes_the_pile_not_the_sand() {
+    let mut base = Universe::new(40, 30);
+    fill(&mut base, 0, 28, 40, 30, Species::Wall);
+    fill(&mut base, 15, 0, 25, 10, Species::Sand);
+    let mut default = base.clone_state();
+    let mut columns = base.clone_state();
+    columns.set_tick_order(ScanOrder::AlternatingColumns);
+    let mut bottom_up = base.clone_state();
+    bottom_up.set_tick_order(ScanOrder::BottomUp);
+    for _ in 0..200 {
+        default.tick();
+        columns.tick();
+        bottom_up.tick();
+    }
+    assert!(default.cells_equal(&columns));
+    assert!(!bottom_up.cells_equal(&columns));
+    assert_eq!(count(&bottom_up, Species::Sand), 100);
+    // settled: no sand is left hanging over an empty cell
+    for x in 0..40 {
+        for y in 0..27 {
+            if species_at(&bottom_up, x, y) == Species::Sand {
+                assert_ne!(species_at(&bottom_up, x, y + 1), Species::Empty);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn burning_wood_smokes() {
+    let mut u = Universe::new(60, 80);
+    fill(&mut u, 0, 78, 60, 80, Species::Wall);
+    fill(&mut u, 25, 70, 35, 78, Species::Wood);
+    fill(&mut u, 25, 69, 35, 70, Species::Fire);
+    let (mut highest, mut left, mut right) = (80, 60, 0);
+    for _ in 0..400 {
+        u.tick();
+        for x in 0..60 {
+            for y in 0..80 {
+                if species_at(&u, x, y) == Species::Smoke {
+                    highest = highest.min(y);
+                    left = left.min(x);
+                    right = right.max(x);
+                }
+            }
+        }
+    }
+    assert!(highest < 40, "smoke only reached {}", highest);
+    assert!(right - left > 15, "smoke spread {}..{}", left, right);
+    for _ in 0..600 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Smoke), 0);
+}
+
+#[wasm_bindgen_test]
+fn brush_shape_stamps_only_the_mask() {
+    let mut u = Universe::new(10, 10);
+    u.paint(6, 5, 1, Species::Stone);
+    let plus = [0, 1, 0, 1, 1, 1, 0, 1, 0];
+    u.apply_brush_shape(5, 5, 3, &plus, Species::Sand);
+    // the stone under the right arm stays put
+    assert_eq!(species_at(&u, 6, 5), Species::Stone);
+    for &(x, y) in [(5, 4), (4, 5), (5, 5), (5, 6)].iter() {
+        assert_eq!(species_at(&u, x, y), Species::Sand);
+    }
+    assert_eq!(count(&u, Species::Sand), 4);
+    // hanging off the corner
+    u.apply_brush_shape(0, 0, 3, &plus, Species::Water);
+    assert_eq!(species_at(&u, 0, 0), Species::Water);
+    assert_eq!(species_at(&u, 1, 0), Species::Water);
+    assert_eq!(species_at(&u, 0, 1), Species::Water);
+    assert_eq!(count(&u, Species::Water), 3);
+}
+
+#[wasm_bindgen_test]
+fn antimatter_annihilates_up_to_the_walls() {
+    let mut u = Universe::new(40, 40);
+    fill(&mut u, 5, 5, 35, 6, Species::Wall);
+    fill(&mut u, 5, 34, 35, 35, Species::Wall);
+    fill(&mut u, 5, 6, 6, 34, Species::Wall);
+    fill(&mut u, 34, 6, 35, 34, Species::Wall);
+    fill(&mut u, 18, 18, 22, 22, Species::Antimatter);
+    fill(&mut u, 6, 6, 34, 34, Species::Sand);
+    let (sand, walls) = (count(&u, Species::Sand), count(&u, Species::Wall));
+    for _ in 0..5 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Antimatter), 0);
+    assert_eq!(count(&u, Species::Sand), sand - 16);
+    // a blob dropped on bare wall just sits there
+    fill(&mut u, 10, 0, 12, 2, Species::Antimatter);
+    for _ in 0..50 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Antimatter), 4);
+    assert_eq!(count(&u, Species::Wall), walls);
+}
+
+#[wasm_bindgen_test]
+fn change_mask_marks_a_falling_grain() {
+    let mut u = Universe::new(10, 10);
+    fill(&mut u, 0, 9, 10, 10, Species::Wall);
+    u.paint(4, 2, 1, Species::Sand);
+    u.tick();
+    let mask = u.change_mask();
+    assert_eq!(mask.len(), 100);
+    let marked: Vec<usize> = (0..100).filter(|&i| mask[i] == 1).collect();
+    assert_eq!(marked, vec![2 * 10 + 4, 3 * 10 + 4]);
+    assert_eq!(u.changed_bounds(), vec![4, 2, 5, 4]);
+    for _ in 0..10 {
+        u.tick();
+    }
+    assert!(u.change_mask().iter().all(|&c| c == 0));
+    assert!(u.changed_bounds().is_empty());
+}
+
+#[wasm_bindgen_test]
+fn dust_sticks_to_tar() {
+    let pool = |liquid: Species| {
+        let mut u = Universe::new(30, 30);
+        fill(&mut u, 0, 28, 30, 30, Species::Wall);
+        fill(&mut u, 9, 24, 10, 28, Species::Wall);
+        fill(&mut u, 20, 24, 21, 28, Species::Wall);
+        fill(&mut u, 10, 25, 20, 28, liquid);
+        fill(&mut u, 12, 5, 18, 6, Species::Dust);
+        fill(&mut u, 14, 3, 16, 4, Species::Dust);
+        for _ in 0..200 {
+            u.tick();
+        }
+        assert_eq!(count(&u, Species::Dust), 8);
+        u
+    };
+    // dust sinks through water...
+    let water = pool(Species::Water);
+    assert_eq!(water.count_in_region(0, 0, 30, 25, Species::Dust), 0);
+    // ...but the first layer stops dead on tar, and the rest piles on it
+    let tar = pool(Species::Tar);
+    assert_eq!(count(&tar, Species::Tar), 30);
+    for x in 12..18 {
+        assert_eq!(species_at(&tar, x, 24), Species::Dust);
+    }
+    assert_eq!(tar.count_in_region(0, 0, 30, 24, Species::Dust), 2);
+}
+
+#[wasm_bindgen_test]
+fn paint_text_writes_glyphs() {
+    let mut u = Universe::new(20, 10);
+    u.paint_text(2, 1, "HI", Species::Sand);
+    let set = |x: i32, y: i32| species_at(&u, x, y) == Species::Sand;
+    // the crossbar of the H and both ends of the I's stem
+    assert!(set(2, 3) && set(3, 3) && set(4, 3));
+    assert!(!set(3, 1));
+    assert!(set(7, 1) && set(7, 5));
+    assert!(!set(6, 3));
+    let xs: Vec<i32> = (0..20).filter(|&x| (0..10).any(|y| set(x, y))).collect();
+    assert_eq!((xs[0], xs[xs.len() - 1]), (2, 8));
+    assert_eq!(count(&u, Species::Sand), 11 + 9);
+    // unknown characters come out blank
+    u.paint_text(0, 7, "~", Species::Water);
+    assert_eq!(count(&u, Species::Water), 0);
+}
+
+#[wasm_bindgen_test]
+fn radioactive_mutates_then_decays() {
+    let mut u = Universe::new(20, 20);
+    fill(&mut u, 0, 18, 20, 20, Species::Wall);
+    fill(&mut u, 10, 14, 11, 15, Species::Radioactive);
+    fill(&mut u, 0, 10, 20, 18, Species::Stone);
+    let stone = count(&u, Species::Stone);
+    for _ in 0..200 {
+        u.tick();
+    }
+    assert_eq!(species_at(&u, 10, 14), Species::Radioactive);
+    let mutated = cells(&u)
+        .iter()
+        .filter(|c| {
+            c.species() != Species::Stone
+                && c.species() != Species::Wall
+                && c.species() != Species::Empty
+                && c.species() != Species::Radioactive
+        })
+        .count();
+    assert!(mutated > 0 && count(&u, Species::Stone) < stone);
+    // only the source's own neighbourhood is touched
+    assert_eq!(u.count_in_region(0, 10, 5, 18, Species::Stone), 40);
+    // and it warms its surroundings
+    let temp = |u: &Universe, x: i32, y: i32| u.temps_slice()[(x * 20 + y) as usize];
+    assert!(temp(&u, 10, 13) > 20, "{}", temp(&u, 10, 13));
+    assert_eq!(temp(&u, 2, 12), 20);
+    for _ in 0..2000 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Radioactive), 0);
+    assert_eq!(species_at(&u, 10, 14), Species::Stone);
+}
+
+#[wasm_bindgen_test]
+fn flood_count_finds_leaks() {
+    let chamber = |leak: bool| {
+        let mut u = Universe::new(20, 20);
+        fill(&mut u, 5, 5, 15, 15, Species::Wall);
+        fill(&mut u, 7, 7, 13, 13, Species::Empty);
+        if leak {
+            // knock a hole in the roof
+            fill(&mut u, 10, 5, 11, 7, Species::Empty);
+        }
+        u
+    };
+    let sealed = chamber(false);
+    assert_eq!(sealed.flood_count(10, 10, Species::Empty), 36);
+    assert_eq!(sealed.flood_count(0, 0, Species::Empty), 300);
+    assert_eq!(sealed.flood_count(6, 6, Species::Wall), 64);
+    assert_eq!(sealed.flood_count(6, 6, Species::Empty), 0);
+    assert_eq!(sealed.flood_count(-1, 6, Species::Wall), 0);
+    let leaking = chamber(true);
+    assert_eq!(leaking.flood_count(10, 10, Species::Empty), 300 + 2 + 36);
+}
+
+#[wasm_bindgen_test]
+fn balloon_rises_to_the_ceiling() {
+    let mut u = Universe::new(20, 40);
+    u.paint(5, 30, 1, Species::Balloon);
+    u.paint(14, 30, 1, Species::Balloon);
+    u.paint(14, 31, 1, Species::Sand);
+    for _ in 0..100 {
+        u.tick();
+    }
+    let tops = |u: &Universe| {
+        cells(u)
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.species() == Species::Balloon)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>()
+    };
+    let at = tops(&u);
+    assert_eq!(at.len(), 2);
+    assert!(at.iter().all(|&i| i % 40 == 0), "balloons at {:?}", at);
+    // the loaded one went straight up and held on to its sand
+    assert_eq!(species_at(&u, 14, 0), Species::Balloon);
+    assert_eq!(species_at(&u, 14, 1), Species::Sand);
+    for _ in 0..20 {
+        u.tick();
+    }
+    assert_eq!(tops(&u), at);
+    // the first spark pops it
+    u.paint(13, 0, 1, Species::Fire);
+    u.tick();
+    assert_eq!(count(&u, Species::Balloon), 1);
+}
+
+#[wasm_bindgen_test]
+fn hot_air_lifts_a_balloon_faster() {
+    let risen = |heat: i16| {
+        let mut u = Universe::new(20, 60);
+        u.paint(10, 50, 1, Species::Balloon);
+        u.apply_heat_source(10, 51, 0, heat);
+        for _ in 0..5 {
+            u.tick();
+        }
+        50 - u.find_species(Species::Balloon).unwrap()[1]
+    };
+    assert_eq!(risen(0), 5);
+    assert!(risen(200) > 5);
+}
+
+#[wasm_bindgen_test]
+fn set_cell_region_writes_a_block() {
+    let mut u = Universe::new(10, 10);
+    let mut data = Vec::new();
+    for n in 0..16u8 {
+        let species = if n % 2 == 0 {
+            Species::Stone
+        } else {
+            Species::Wall
+        };
+        data.extend_from_slice(&[species as u8, n, 100 + n]);
+    }
+    u.set_cell_region(3, 2, 4, 4, &data).unwrap();
+    let all = cells(&u);
+    for n in 0..16 {
+        let (x, y) = (3 + n % 4, 2 + n / 4);
+        let cell = all[(x * 10 + y) as usize];
+        assert_eq!(cell.ra(), n as u8);
+        assert_eq!(cell.rb(), 100 + n as u8);
+    }
+    assert_eq!(count(&u, Species::Stone), 8);
+    assert_eq!(count(&u, Species::Wall), 8);
+    // hanging off the bottom right corner only the overlap lands
+    u.set_cell_region(8, 8, 4, 4, &data).unwrap();
+    assert_eq!(count(&u, Species::Stone) + count(&u, Species::Wall), 20);
+    assert_eq!(species_at(&u, 9, 9), Species::Wall);
+    // a block with a bogus species is turned away whole
+    data[3] = 200;
+    assert!(u.set_cell_region(0, 0, 4, 4, &data).is_err());
+    assert_eq!(species_at(&u, 0, 0), Species::Empty);
+    // as is one too big to size without overflowing
+    assert!(u.set_cell_region(0, 0, i32::MAX, i32::MAX, &data).is_err());
+}
+
+#[wasm_bindgen_test]
+fn dynamite_tunnels_the_way_it_points() {
+    let mut u = Universe::new(60, 30);
+    u.paint(10, 15, 1, Species::Dynamite);
+    u.aim(10, 15, 1, 0);
+    u.paint(9, 15, 1, Species::Fire);
+    fill(&mut u, 0, 0, 60, 30, Species::Stone);
+    let mut ticks = 0;
+    while count(&u, Species::Dynamite) > 0 && ticks < 20 {
+        u.tick();
+        ticks += 1;
+    }
+    assert_eq!(count(&u, Species::Dynamite), 0);
+    // the blast pressure falls off along the cone
+    let pressure = |u: &Universe, x: i32| u.burns_slice()[(x * 30 + 15) as usize].pressure();
+    assert!(pressure(&u, 12) > pressure(&u, 30));
+    assert!(pressure(&u, 30) > 0);
+    assert_eq!(pressure(&u, 8), 0);
+    for _ in ticks..20 {
+        u.tick();
+    }
+    for x in 11..35 {
+        assert_eq!(species_at(&u, x, 15), Species::Empty, "at {}", x);
+    }
+    // it widens a little towards the far end, and nothing goes backwards
+    assert_eq!(species_at(&u, 12, 14), Species::Stone);
+    assert_eq!(species_at(&u, 30, 13), Species::Empty);
+    assert_eq!(species_at(&u, 30, 5), Species::Stone);
+    assert_eq!(species_at(&u, 8, 15), Species::Stone);
+    assert_eq!(species_at(&u, 40, 15), Species::Stone);
+}
+
+#[wasm_bindgen_test]
+fn busy_grids_get_fewer_substeps() {
+    let mut calm = Universe::new(40, 40);
+    fill(&mut calm, 0, 30, 40, 40, Species::Stone);
+    calm.paint(20, 10, 1, Species::Sand);
+    calm.tick();
+    // grains scattered everywhere all fall at once
+    let mut busy = Universe::new_with_density(40, 40, 0.3, 0.0);
+    busy.tick();
+    assert!(
+        calm.recommended_substeps() > busy.recommended_substeps(),
+        "calm {} busy {}",
+        calm.recommended_substeps(),
+        busy.recommended_substeps()
+    );
+    assert_eq!(calm.recommended_substeps(), 4);
+    assert_eq!(busy.recommended_substeps(), 1);
+}
+
+#[wasm_bindgen_test]
+fn glued_stones_blow_away_together() {
+    let pair = |middle: Species| {
+        let mut u = Universe::new(40, 20);
+        fill(&mut u, 0, 13, 40, 14, Species::Wall);
+        u.paint(10, 10, 1, Species::Stone);
+        u.paint(10, 11, 1, middle);
+        u.paint(10, 12, 1, Species::Stone);
+        for _ in 0..100 {
+            u.tick();
+        }
+        // a gale along the top stone's row only
+        for x in 0..40 {
+            u.add_wind(x, 10, 100, 0);
+        }
+        for _ in 0..10 {
+            u.tick();
+        }
+        (0..40)
+            .flat_map(|x| (0..20).map(move |y| (x, y)))
+            .filter(|&(x, y)| species_at(&u, x, y) == Species::Stone)
+            .collect::<Vec<_>>()
+    };
+    // the glue holds the stack together, so the bottom is dragged along too
+    let glued = pair(Species::Glue);
+    assert_eq!(glued.len(), 2);
+    assert!(glued[0].0 > 15, "{:?}", glued);
+    assert_eq!(glued, vec![(glued[0].0, 10), (glued[0].0, 12)]);
+    // wood doesn't bond: the top stone is blown off and the bottom stays
+    let loose = pair(Species::Wood);
+    assert_eq!(loose, vec![(10, 12), (11, 12)]);
+}
+
+#[wasm_bindgen_test]
+fn replay_reproduces_a_session() {
+    let mut u = Universe::new(30, 30);
+    assert!(u.export_replay().is_empty());
+    u.start_recording();
+    u.paint(15, 5, 6, Species::Sand);
+    for _ in 0..20 {
+        u.tick();
+    }
+    u.paint_with_velocity(10, 10, 3, Species::Water, 60, -20);
+    u.apply_vortex(15, 15, 40.0, 6);
+    for _ in 0..30 {
+        u.tick();
+    }
+    let log = u.export_replay();
+
+    let mut copy = Universe::new(30, 30);
+    copy.paint(3, 3, 4, Species::Stone);
+    copy.replay(&log).unwrap();
+    assert!(copy.cells_equal(&u));
+    assert_eq!(
+        copy.region_checksum(0, 0, 30, 30),
+        u.region_checksum(0, 0, 30, 30)
+    );
+    assert_eq!(copy.get_wind_at(15, 10), u.get_wind_at(15, 10));
+}
+
+#[wasm_bindgen_test]
+fn replay_logs_uniform_wind_once() {
+    let mut u = Universe::new(40, 40);
+    u.start_recording();
+    u.apply_uniform_wind(3.0, 4.0, 50.0);
+    u.tick();
+    let log = u.export_replay();
+    // the size header, one uniform wind entry and one tick
+    assert_eq!(log.len(), 4 + 4 + 2);
+
+    let mut copy = Universe::new(40, 40);
+    copy.replay(&log).unwrap();
+    assert_eq!(copy.get_wind_at(7, 31), [30, 40]);
+    assert_eq!(copy.get_wind_at(7, 31), u.get_wind_at(7, 31));
+
+    // and a vortex as one entry too
+    let mut u = Universe::new(40, 40);
+    u.start_recording();
+    u.apply_vortex(20, 20, 40.0, 8);
+    let log = u.export_replay();
+    assert_eq!(log.len(), 4 + 12);
+    let mut copy = Universe::new(40, 40);
+    copy.replay(&log).unwrap();
+    assert_eq!(copy.get_wind_at(20, 16), u.get_wind_at(20, 16));
+    assert_ne!(copy.get_wind_at(20, 16), [0, 0]);
+}
+
+#[wasm_bindgen_test]
+fn phosphorus_lights_when_uncovered() {
+    let mut u = Universe::new(40, 30);
+    // one box sealed with a wall lid, one topped up with water
+    for &x0 in [5, 25].iter() {
+        fill(&mut u, x0, 10, x0 + 10, 20, Species::Wall);
+        fill(&mut u, x0 + 1, 15, x0 + 9, 19, Species::Empty);
+        fill(&mut u, x0 + 1, 15, x0 + 9, 19, Species::Phosphorus);
+    }
+    fill(&mut u, 26, 11, 34, 15, Species::Empty);
+    fill(&mut u, 26, 11, 34, 15, Species::Water);
+    let stock = count(&u, Species::Phosphorus);
+    assert_eq!(stock, 64);
+    for _ in 0..100 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Phosphorus), stock);
+    assert_eq!(count(&u, Species::Fire), 0);
+    // lift the lid off the sealed box
+    fill(&mut u, 6, 10, 14, 15, Species::Empty);
+    let mut fire = 0;
+    for _ in 0..20 {
+        u.tick();
+        fire = fire.max(count(&u, Species::Fire));
+    }
+    assert!(fire > 10, "{} fire", fire);
+    assert!(count(&u, Species::Phosphorus) < stock);
+    // the submerged batch is untouched
+    assert_eq!(u.count_in_region(25, 10, 35, 20, Species::Phosphorus), 32);
+}
+
+#[wasm_bindgen_test]
+fn disabled_species_cant_be_painted() {
+    let mut u = Universe::new(20, 20);
+    fill(&mut u, 0, 18, 20, 20, Species::Wall);
+    u.paint(5, 17, 1, Species::Fire);
+    u.set_species_enabled(Species::Fire, false);
+    u.paint(10, 10, 5, Species::Fire);
+    u.paint_ellipse(15, 5, 2, 1, Species::Fire);
+    assert_eq!(count(&u, Species::Fire), 1);
+    u.paint(10, 10, 3, Species::Sand);
+    assert!(count(&u, Species::Sand) > 0);
+    // scenes aren't limited by the palette
+    u.set_species_enabled(Species::Wall, false);
+    u.apply_preset(Preset::EmptyWithFloor);
+    assert_eq!(count(&u, Species::Wall), 40);
+    u.set_species_enabled(Species::Fire, true);
+    u.paint(10, 10, 1, Species::Fire);
+    assert_eq!(count(&u, Species::Fire), 1);
+}
+
+#[wasm_bindgen_test]
+fn plants_grow_faster_in_soil() {
+    let bed = |ground: Species| {
+        let mut u = Universe::new(30, 30);
+        fill(&mut u, 0, 28, 30, 30, Species::Wall);
+        fill(&mut u, 0, 25, 30, 28, ground);
+        fill(&mut u, 10, 24, 20, 25, Species::Plant);
+        for _ in 0..600 {
+            u.tick();
+        }
+        u
+    };
+    let sand = bed(Species::Sand);
+    let soil = bed(Species::Soil);
+    assert!(
+        count(&soil, Species::Plant) > count(&sand, Species::Plant) + 20,
+        "soil {} sand {}",
+        count(&soil, Species::Plant),
+        count(&sand, Species::Plant)
+    );
+    // the soil under the plants is worn out to sand
+    assert!(
+        count(&soil, Species::Sand) > 0,
+        "{}",
+        count(&soil, Species::Sand)
+    );
+    assert!(count(&soil, Species::Soil) < 90);
+}
+
+#[wasm_bindgen_test]
+fn query_line_hits_the_first_obstacle() {
+    let mut u = Universe::new(30, 30);
+    fill(&mut u, 20, 0, 21, 30, Species::Wall);
+    u.paint(2, 2, 1, Species::Sand);
+    assert_eq!(u.query_line(2, 2, 28, 15), Some([20, 11]));
+    assert_eq!(u.query_line(2, 2, 28, 2), Some([20, 2]));
+    assert_eq!(u.query_line(2, 2, 19, 29), None);
+    // the start doesn't count, but anything after it does
+    assert_eq!(u.query_line(5, 5, 0, 0), Some([2, 2]));
+    assert_eq!(u.query_line(5, 5, 5, 5), None);
+}
+
+#[wasm_bindgen_test]
+fn confetti_flutters_down_and_settles() {
+    let mut u = Universe::new(40, 40);
+    fill(&mut u, 0, 38, 40, 40, Species::Wall);
+    fill(&mut u, 0, 35, 40, 38, Species::Sand);
+    for _ in 0..20 {
+        u.tick();
+    }
+    let sand = u.species_mask(Species::Sand);
+    fill(&mut u, 18, 2, 22, 4, Species::Confetti);
+    let mut colours = std::collections::HashSet::new();
+    for _ in 0..150 {
+        u.tick();
+        for c in cells(&u)
+            .iter()
+            .filter(|c| c.species() == Species::Confetti)
+        {
+            colours.insert(c.ra());
+        }
+    }
+    assert!(colours.len() > 4);
+    assert_eq!(count(&u, Species::Confetti), 8);
+    // it all came to rest on the sand, spread out, with the sand untouched
+    let xs: Vec<i32> = (0..40)
+        .filter(|&x| species_at(&u, x, 34) == Species::Confetti)
+        .collect();
+    assert!(xs.len() > 4 && xs[xs.len() - 1] - xs[0] > 4, "{:?}", xs);
+    assert_eq!(u.species_mask(Species::Sand), sand);
+    for _ in 0..300 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Confetti), 0);
+}
+
+#[wasm_bindgen_test]
+fn compact_undo_keeps_both_ends() {
+    let mut u = Universe::new(10, 60);
+    let original = u.clone_state();
+    for y in 0..50 {
+        u.push_undo();
+        u.paint(5, y, 1, Species::Wall);
+    }
+    assert_eq!(u.undo_depth(), 50);
+    u.compact_undo(10);
+    assert_eq!(u.undo_depth(), 10);
+    // the first step back only undoes the very last stroke
+    u.pop_undo();
+    assert_eq!(count(&u, Species::Wall), 49);
+    let mut steps = 1;
+    while u.undo_depth() > 0 {
+        u.pop_undo();
+        steps += 1;
+    }
+    assert_eq!(steps, 10);
+    assert!(u.cells_equal(&original));
+}
+
+#[wasm_bindgen_test]
+fn membrane_passes_fresh_water_only() {
+    let mut u = Universe::new(30, 30);
+    fill(&mut u, 0, 28, 30, 30, Species::Wall);
+    fill(&mut u, 0, 0, 1, 28, Species::Wall);
+    fill(&mut u, 29, 0, 30, 28, Species::Wall);
+    fill(&mut u, 15, 0, 16, 28, Species::Membrane);
+    // a deep fresh pool on the left, a shallow salty one on the right
+    fill(&mut u, 1, 8, 15, 28, Species::Water);
+    fill(&mut u, 16, 24, 29, 28, Species::Water);
+    u.dissolve(22, 26, 12);
+    let salty = |u: &Universe, x0: i32, x1: i32| {
+        cells(u)
+            .iter()
+            .enumerate()
+            .filter(|&(i, c)| {
+                let x = i as i32 / 30;
+                x >= x0 && x < x1 && c.species() == Species::Water && c.rb() & 128 != 0
+            })
+            .count()
+    };
+    let right = |u: &Universe| {
+        (16..29)
+            .flat_map(|x| (0..28).map(move |y| (x, y)))
+            .filter(|&(x, y)| species_at(u, x, y) == Species::Water)
+            .count()
+    };
+    let before = (right(&u), salty(&u, 16, 29));
+    assert!(before.1 > 10);
+    for _ in 0..600 {
+        u.tick();
+    }
+    assert!(right(&u) > before.0 + 20, "{} -> {}", before.0, right(&u));
+    // the salt mixes through its own side but never crosses back
+    assert!(salty(&u, 16, 29) > 0);
+    assert_eq!(salty(&u, 1, 15), 0);
+    assert_eq!(count(&u, Species::Water), 14 * 20 + 13 * 4);
+    assert_eq!(count(&u, Species::Membrane), 28);
+}
+
+#[wasm_bindgen_test]
+fn dirty_bounds_span_several_ticks() {
+    let mut u = Universe::new(40, 40);
+    fill(&mut u, 0, 38, 40, 40, Species::Wall);
+    u.reset_dirty_bounds();
+    assert!(u.dirty_bounds().is_empty());
+    u.paint(5, 36, 1, Species::Sand);
+    u.tick();
+    u.paint(30, 10, 1, Species::Stone);
+    u.tick();
+    u.tick();
+    u.paint(20, 36, 1, Species::Wall);
+    u.tick();
+    // the last tick alone only moved the stone
+    assert_eq!(u.changed_bounds()[0], 30);
+    // the stone has fallen to y 13 by now, and where it was painted is back
+    // to empty like at the reset
+    assert_eq!(u.dirty_bounds(), vec![5, 13, 31, 38]);
+    u.reset_dirty_bounds();
+    assert!(u.dirty_bounds().is_empty());
+}
+
+#[wasm_bindgen_test]
+fn frost_freezes_a_pool_and_fire_drives_it_back() {
+    let mut u = Universe::new(40, 20);
+    fill(&mut u, 0, 18, 40, 20, Species::Stone);
+    fill(&mut u, 0, 0, 1, 18, Species::Wall);
+    fill(&mut u, 39, 0, 40, 18, Species::Wall);
+    fill(&mut u, 2, 16, 39, 18, Species::Water);
+    u.paint(1, 17, 1, Species::Frost);
+    let ice_xs = |u: &Universe| {
+        (0..40)
+            .filter(|&x| (16..18).any(|y| species_at(u, x, y) == Species::Ice))
+            .collect::<Vec<i32>>()
+    };
+    for _ in 0..300 {
+        u.tick();
+    }
+    // a sheet of ice growing out from the source end of the pool
+    let frozen = count(&u, Species::Ice);
+    let xs = ice_xs(&u);
+    assert!(frozen > 10, "{} ice", frozen);
+    assert_eq!(xs, (2..2 + xs.len() as i32).collect::<Vec<i32>>());
+    assert!(count(&u, Species::Frost) > 1);
+    for _ in 0..40 {
+        fill(&mut u, 1, 14, 39, 16, Species::Fire);
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Frost), 0);
+    assert!(count(&u, Species::Ice) < frozen);
+    assert_eq!(count(&u, Species::Ice) + count(&u, Species::Water), 37 * 2);
+}
+
+#[wasm_bindgen_test]
+fn simulation_scale_is_cheaper_and_still_settles() {
+    // a slab of sand dropping through open air: at scale 2 it falls as
+    // 2x2 lumps, running far fewer updates but falling just as fast
+    let drop = |scale: u8| {
+        let mut u = Universe::new(100, 100);
+        fill(&mut u, 0, 60, 100, 100, Species::Wall);
+        fill(&mut u, 0, 0, 100, 20, Species::Sand);
+        u.set_simulation_scale(scale);
+        let mut updates = 0;
+        for _ in 0..30 {
+            u.tick();
+            updates += u.last_tick_updates();
+        }
+        // the lowest grain, well clear of the floor at 60
+        let front = (0..60)
+            .rev()
+            .find(|&y| (0..100).any(|x| species_at(&u, x, y) == Species::Sand));
+        (updates, front.unwrap_or(0))
+    };
+    let (full, fine_front) = drop(1);
+    let (half, coarse_front) = drop(2);
+    assert!(half * 2 < full, "{} vs {} updates", half, full);
+    assert!(fine_front > 40 && fine_front < 55, "{}", fine_front);
+    assert!(
+        (coarse_front - fine_front).abs() <= 3,
+        "{} vs {}",
+        coarse_front,
+        fine_front
+    );
+
+    // a block of sand still ends up heaped on the floor
+    let mut u = Universe::new(40, 40);
+    fill(&mut u, 0, 38, 40, 40, Species::Wall);
+    fill(&mut u, 15, 0, 25, 10, Species::Sand);
+    u.set_simulation_scale(2);
+    for _ in 0..150 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Sand), 100);
+    for x in 0..40 {
+        for y in 0..37 {
+            if species_at(&u, x, y) == Species::Sand {
+                assert_ne!(species_at(&u, x, y + 1), Species::Empty, "{} {}", x, y);
+            }
+        }
+    }
+    assert_eq!(species_at(&u, 20, 37), Species::Sand);
+}
+
+#[wasm_bindgen_test]
+fn light_is_blocked_by_walls() {
+    let mut u = Universe::new(40, 40);
+    u.paint(5, 20, 1, Species::Light);
+    fill(&mut u, 10, 10, 11, 30, Species::Wall);
+    u.tick();
+    let light = |x: i32, y: i32| u.light_slice()[(x * 40 + y) as usize];
+    assert_eq!(light(5, 20), 255);
+    // dimmer further out, and the lit face of the wall itself
+    assert!(light(8, 20) > light(5, 26) && light(5, 26) > 0);
+    assert!(light(10, 20) > 0);
+    for x in 11..16 {
+        for y in 17..24 {
+            assert_eq!(light(x, y), 0, "{} {}", x, y);
+        }
+    }
+    // out of reach in the open is dark too
+    assert_eq!(light(35, 20), 0);
+}
+
+#[wasm_bindgen_test]
+fn erase_species_leaves_the_rest() {
+    let mut u = Universe::new(30, 30);
+    fill(&mut u, 0, 0, 10, 5, Species::Sand);
+    fill(&mut u, 10, 0, 20, 5, Species::Fire);
+    let sand = u.species_mask(Species::Sand);
+    u.erase_species(Species::Fire);
+    assert_eq!(count(&u, Species::Fire), 0);
+    assert_eq!(count(&u, Species::Empty), 30 * 30 - 50);
+    assert_eq!(u.species_mask(Species::Sand), sand);
+}
+
+#[wasm_bindgen_test]
+fn conveyor_carries_sand_along() {
+    let mut u = Universe::new(40, 20);
+    fill(&mut u, 5, 15, 30, 16, Species::Conveyor);
+    u.paint(6, 14, 1, Species::Sand);
+    u.paint(10, 14, 1, Species::Sand);
+    for _ in 0..10 {
+        u.tick();
+    }
+    assert_eq!(species_at(&u, 16, 14), Species::Sand);
+    assert_eq!(species_at(&u, 20, 14), Species::Sand);
+    assert_eq!(count(&u, Species::Sand), 2);
+    // turned around, it brings them back
+    u.set_conveyor(29, 15, -1);
+    for _ in 0..10 {
+        u.tick();
+    }
+    assert_eq!(species_at(&u, 6, 14), Species::Sand);
+    assert_eq!(species_at(&u, 10, 14), Species::Sand);
+    // and off the end they fall
+    for _ in 0..20 {
+        u.tick();
+    }
+    assert_eq!(species_at(&u, 4, 19), Species::Sand);
+}
+
+#[wasm_bindgen_test]
+fn region_diff_stays_in_its_tile() {
+    let mut peer = Universe::new(32, 32);
+    let mut u = Universe::new(32, 32);
+    let before = peer.cells_slice().to_vec();
+    fill(&mut peer, 17, 1, 20, 4, Species::Wall);
+    let diff = peer.encode_diff(&before);
+    assert_eq!(diff.len(), 9 * 7);
+    // tile (1, 0) of 16x16 owns x 16..32, y 0..16
+    u.paint(5, 5, 1, Species::Stone);
+    u.apply_region_diff(1, 0, 16, &diff).unwrap();
+    assert_eq!(count(&u, Species::Wall), 9);
+    assert_eq!(species_at(&u, 18, 2), Species::Wall);
+    assert_eq!(species_at(&u, 5, 5), Species::Stone);
+
+    // a write into someone else's tile rejects the lot
+    let before = peer.cells_slice().to_vec();
+    peer.paint(2, 2, 1, Species::Sand);
+    peer.paint(20, 10, 1, Species::Sand);
+    assert!(u
+        .apply_region_diff(1, 0, 16, &peer.encode_diff(&before))
+        .is_err());
+    assert_eq!(count(&u, Species::Sand), 0);
+    assert_eq!(count(&u, Species::Empty), 32 * 32 - 10);
+
+    // and so does a species byte that isn't one
+    let mut bad = diff.clone();
+    bad[4] = 200;
+    assert!(u.apply_region_diff(1, 0, 16, &bad).is_err());
+    assert_eq!(count(&u, Species::Wall), 9);
+}
+
+#[wasm_bindgen_test]
+fn species_from_u8_rejects_unknown_numbers() {
+    assert_eq!(Species::from_u8(2), Some(Species::Sand));
+    assert_eq!(Species::from_u8(64), Some(Species::Mycelium));
+    assert_eq!(Species::from_u8(10), None);
+    assert_eq!(Species::from_u8(21), None);
+    assert_eq!(Species::from_u8(200), None);
+}
+
+#[wasm_bindgen_test]
+fn egg_hatches_a_mite() {
+    let mut u = Universe::new(30, 30);
+    u.set_egg_hatch(20);
+    u.paint(15, 20, 1, Species::Egg);
+    for _ in 0..15 {
+        u.tick();
+    }
+    // it drops to the floor and waits there
+    assert_eq!(species_at(&u, 15, 29), Species::Egg);
+    assert_eq!(count(&u, Species::Mite), 0);
+    for _ in 0..5 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Egg), 0);
+    assert_eq!(u.find_species(Species::Mite), Some([15, 29]));
+    let mut moved = false;
+    for _ in 0..100 {
+        u.tick();
+        moved |= u.find_species(Species::Mite) != Some([15, 29]);
+    }
+    assert!(moved);
+}
+
+#[wasm_bindgen_test]
+fn total_ticks_outlasts_the_generation_wrap() {
+    let mut u = Universe::new(10, 10);
+    assert_eq!(u.total_ticks(), 0);
+    for n in 1..=300u64 {
+        u.tick();
+        assert_eq!(u.total_ticks(), n);
+    }
+    u.tick_with_hook(|_| {});
+    assert_eq!(u.total_ticks(), 301);
+}
+
+#[wasm_bindgen_test]
+fn spores_seed_fungus_downwind() {
+    let mut u = Universe::new(60, 30);
+    fill(&mut u, 0, 12, 10, 13, Species::Wall);
+    fill(&mut u, 2, 10, 8, 12, Species::Fungus);
+    fill(&mut u, 30, 29, 60, 30, Species::Wood);
+    for x in 0..60 {
+        for y in 0..29 {
+            u.add_wind(x, y, 30, 0);
+        }
+    }
+    for _ in 0..400 {
+        u.tick();
+    }
+    let downwind = (30..60)
+        .flat_map(|x| (0..30).map(move |y| (x, y)))
+        .filter(|&(x, y)| species_at(&u, x, y) == Species::Fungus)
+        .count();
+    assert!(downwind > 0);
+}
+
+#[wasm_bindgen_test]
+fn population_cap_halts_plant_growth() {
+    let grow = |cap: u32| {
+        let mut u = Universe::new(30, 30);
+        u.paint(15, 20, 1, Species::Plant);
+        fill(&mut u, 0, 10, 30, 30, Species::Water);
+        u.set_population_cap(Species::Plant, cap);
+        for _ in 0..300 {
+            u.tick();
+        }
+        u
+    };
+    assert!(count(&grow(u32::MAX), Species::Plant) > 60);
+    let mut u = grow(30);
+    let plants = count(&u, Species::Plant);
+    // a single update can add a cell or two past the cap, no more
+    assert!(plants >= 30 && plants <= 32, "{}", plants);
+    for _ in 0..100 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Species::Plant), plants);
+    // capped plants still burn
+    u.erase_species(Species::Water);
+    fill(&mut u, 0, 0, 30, 30, Species::Fire);
+    for _ in 0..60 {
+        u.tick();
+    }
+    assert!(count(&u, Species::Plant) < plants);
+}
+
+#[wasm_bindgen_test]
+fn battery_drives_a_conveyor_until_flat() {
+    let mut u = Universe::new(90, 20);
+    fill(&mut u, 5, 15, 85, 16, Species::Conveyor);
+    u.paint(4, 15, 1, Species::Battery);
+    u.paint(6, 14, 1, Species::Sand);
+    for _ in 0..80 {
+        u.tick();
+    }
+    // about one cell per tick of charge, then the belt stops dead
+    let [x, y] = u.find_species(Species::Sand).unwrap();
+    assert_eq!(y, 14);
+    assert!(x > 50 && x < 60, "{}", x);
+    for _ in 0..40 {
+        u.tick();
+    }
+    assert_eq!(u.find_species(Species::Sand), Some([x, 14]));
+    // a pulse tops it back up
+    u.paint(4, 14, 1, Species::Electric);
+    for _ in 0..5 {
+        u.tick();
+    }
+    assert!(u.find_species(Species::Sand).unwrap()[0] > x);
+}
+
+#[wasm_bindgen_test]
+fn battery_sends_electric_pulses_down_a_wire() {
+    let mut u = Universe::new(20, 20);
+    u.paint(5, 10, 1, Species::Battery);
+    fill(&mut u, 7, 10, 15, 11, Species::Charge);
+    let mut sparks = 0;
+    for _ in 0..30 {
+        u.tick();
+        sparks += count(&u, Species::Electric);
+    }
+    assert!(sparks > 0);
+    assert_eq!(count(&u, Species::Battery), 1);
+}
+
+#[wasm_bindgen_test]
+fn needle_pops_a_balloon_on_contact() {
+    // the row the balloon was last seen on, and the universe 60 ticks on
+    let rise_into = |ceiling: Species| {
+        let mut u = Universe::new(20, 30);
+        fill(&mut u, 0, 5, 20, 6, ceiling);
+        u.paint(10, 25, 1, Species::Balloon);
+        let mut last = 25;
+        for _ in 0..60 {
+            u.tick();
+            if let Some([_, y]) = u.find_species(Species::Balloon) {
+                last = y;
+            }
+        }
+        (last, u)
+    };
+    let (last, wall) = rise_into(Species::Wall);
+    assert_eq!(last, 6);
+    assert_eq!(count(&wall, Species::Balloon), 1);
+    // it only got as far as touching the needles
+    let (last, needles) = rise_into(Species::Needle);
+    assert!(last >= 6, "{}", last);
+    assert_eq!(count(&needles, Species::Balloon), 0);
+    assert_eq!(count(&needles, Species::Needle), 20);
+}
+
+#[wasm_bindgen_test]
+fn heat_source_spreads_and_boils_water() {
+    let mut u = Universe::new(30, 30);
+    fill(&mut u, 0, 20, 30, 30, Species::Water);
+    let water = count(&u, Species::Water);
+    let temp = |u: &Universe, x: i32, y: i32| u.temps_slice()[(x * 30 + y) as usize];
+    assert_eq!(temp(&u, 15, 5), 20);
+    u.apply_heat_source(15, 5, 2, 400);
+    assert_eq!(temp(&u, 15, 5), 420);
+    assert_eq!(temp(&u, 15, 8), 20);
+    for _ in 0..10 {
+        u.tick();
+    }
+    // it has spread past the brush and is cooling in the middle
+    assert!(temp(&u, 15, 8) > 20);
+    assert!(temp(&u, 15, 5) < 420);
+
+    u.apply_heat_source(15, 25, 3, 300);
+    for _ in 0..20 {
+        u.tick();
+    }
+    assert!(count(&u, Species::Water) < water);
+}
+
+#[wasm_bindgen_test]
+fn warmed_cells_cool_back_to_ambient() {
+    let mut u = Universe::new(20, 20);
+    u.apply_heat_source(10, 10, 1, 40);
+    u.apply_heat_source(3, 3, 0, -6);
+    for _ in 0..400 {
+        u.tick();
+    }
+    assert!(u.temps_slice().iter().all(|&t| t == 20));
+}
+
+#[wasm_bindgen_test]
+fn buried_dirt_packs_and_holds() {
+    let shelf = |buried: bool| {
+        let mut u = Universe::new(40, 40);
+        fill(&mut u, 15, 36, 25, 37, Species::Wall);
+        fill(&mut u, 15, 30, 25, 36, Species::Dirt);
+        // sides to keep the load from sliding off the top
+        fill(&mut u, 14, 10, 15, 31, Species::Wall);
+        fill(&mut u, 25, 10, 26, 31, Species::Wall);
+        if buried {
+            fill(&mut u, 15, 10, 25, 30, Species::Sand);
+        }
+        for _ in 0..100 {
+            u.tick();
+        }
+        u
+    };
+    let spilled = |u: &Universe| {
+        (0..40)
+            .flat_map(|x| (37..40).map(move |y| (x, y)))
+            .filter(|&(x, y)| species_at(u, x, y) == Species::Dirt)
+            .count()
+    };
+    let loose = shelf(false);
+    assert!(spilled(&loose) > 0);
+    let buried = shelf(true);
+    assert_eq!(spilled(&buried), 0);
+    assert_eq!(count(&buried, Species::Dirt), 60);
+    assert_eq!(cells(&buried)[(15 * 40 + 35) as usize].rb(), 1);
+
+    // knock the shelf out and the packed dirt crumbles and falls
+    let mut u = buried;
+    fill(&mut u, 15, 36, 25, 37, Species::Empty);
+    for _ in 0..20 {
+        u.tick();
+    }
+    assert!(spilled(&u) > 0);
+}
+
+#[wasm_bindgen_test]
+fn created_counts_tally_every_flare_up() {
+    let mut u = Universe::new(40, 40);
+    u.paint(5, 5, 1, Species::Sand);
+    fill(&mut u, 10, 25, 30, 35, Species::Gas);
+    // painting isn't creating
+    assert!(u.created_counts().iter().all(|&n| n == 0));
+    let mut peak = 0;
+    for _ in 0..4 {
+        fill(&mut u, 10, 25, 30, 35, Species::Gas);
+        u.paint(20, 36, 1, Species::Fire);
+        for _ in 0..60 {
+            u.tick();
+            peak = peak.max(count(&u, Species::Fire));
+        }
+    }
+    let created = u.created_counts();
+    assert_eq!(created.len(), 256);
+    assert!(
+        created[Species::Fire as usize] as usize > peak,
+        "{} created, peak {}",
+        created[Species::Fire as usize],
+        peak
+    );
+    // and neither is falling
+    assert_eq!(created[Species::Sand as usize], 0);
+}
+
+#[wasm_bindgen_test]
+fn quicklime_slakes_hot() {
+    let mut u = Universe::new(30, 30);
+    fill(&mut u, 5, 25, 25, 30, Species::Quicklime);
+    fill(&mut u, 10, 15, 20, 18, Species::Water);
+    let water = count(&u, Species::Water);
+    let mut hottest = 0;
+    for _ in 0..40 {
+        u.tick();
+        hottest = hottest.max(*u.temps_slice().iter().max().unwrap());
+    }
+    assert!(hottest > 100, "{}", hottest);
+    assert!(count(&u, Species::SlakedLime) > 0);
+    assert!(count(&u, Species::Water) < water);
+    assert_eq!(
+        count(&u, Species::Quicklime) + count(&u, Species::SlakedLime),
+        100
+    );
+}
+
+#[wasm_bindgen_test]
+fn wind_brush_fades_to_its_edge() {
+    let mut u = Universe::new(40, 40);
+    u.paint_wind_brush(20, 20, 5, 100, -40);
+    assert_eq!(u.get_wind_at(20, 20), [100, -40]);
+    let edge = u.get_wind_at(25, 20);
+    assert!(edge[0] > 0 && edge[0] < 50, "{:?}", edge);
+    assert!(edge[1] < 0 && edge[1] > -40, "{:?}", edge);
+    assert_eq!(u.get_wind_at(26, 20), [0, 0]);
+    assert_eq!(u.get_wind_at(24, 24), [0, 0]);
+
+    // dust in the middle of it gets blown along
+    let mut u = Universe::new(40, 40);
+    fill(&mut u, 0, 39, 40, 40, Species::Wall);
+    u.paint(20, 38, 1, Species::Dust);
+    u.paint_wind_brush(20, 38, 3, 60, 0);
+    u.tick();
+    assert_eq!(species_at(&u, 20, 38), Species::Empty);
+
+    // a recording logs the stroke once, and replays to the same field
+    let mut u = Universe::new(40, 40);
+    u.start_recording();
+    u.paint_wind_brush(20, 20, 5, 100, -40);
+    let log = u.export_replay();
+    assert_eq!(log.len(), 4 + 10);
+    let mut copy = Universe::new(40, 40);
+    copy.replay(&log).unwrap();
+    assert_eq!(copy.get_wind_at(23, 18), u.get_wind_at(23, 18));
+    assert_ne!(copy.get_wind_at(23, 18), [0, 0]);
+}
+
+#[wasm_bindgen_test]
+fn mycelium_fruits_at_the_surface() {
+    let mut u = Universe::new(40, 30);
+    fill(&mut u, 0, 20, 40, 23, Species::Dirt);
+    fill(&mut u, 0, 23, 40, 30, Species::Soil);
+    fill(&mut u, 20, 22, 21, 23, Species::Empty);
+    u.paint(20, 22, 1, Species::Mycelium);
+    for _ in 0..300 {
+        u.tick();
+    }
+    let mycelium = |u: &Universe| {
+        (0..40)
+            .flat_map(|x| (0..30).map(move |y| (x, y)))
+            .filter(|&(x, y)| species_at(u, x, y) == Species::Mycelium)
+            .collect::<Vec<_>>()
+    };
+    // spread out through the dirt, but not into the air above it, and fed
+    // off the soil without eating into it
+    assert!(mycelium(&u).len() > 5);
+    assert!(mycelium(&u).iter().all(|&(_, y)| y >= 20 && y < 23));
+    assert_eq!(count(&u, Species::Soil) + count(&u, Species::Sand), 280);
+    for _ in 0..1000 {
+        u.tick();
+    }
+    assert!(count(&u, Species::Soil) < 280);
+    assert_eq!(count(&u, Species::Soil) + count(&u, Species::Sand), 280);
+    assert!(mycelium(&u).iter().all(|&(_, y)| y >= 20));
+    // it carried on along the top, right under open air
+    let surface = mycelium(&u).iter().filter(|&&(_, y)| y == 20).count();
+    assert!(surface > 5, "{}", surface);
+    let fungus = u.find_species(Species::Fungus).unwrap();
+    assert!(fungus[1] < 20, "{:?}", fungus);
+}